@@ -1,7 +1,9 @@
 pub mod rux_debug;
 pub mod rux_fires;
+pub mod rux_hosts;
 pub mod rux_paths;
 pub mod rux_rands;
+pub mod rux_tasks;
 pub mod rux_texts;
 pub mod rux_times;
 pub mod rux_winds;
@@ -102,6 +102,15 @@ pub fn put_archive() {
   set_archive(true);
 }
 
+pub fn archive_flush() {
+  if is_archive() {
+    if let Ok(mut file) = ARCFILE.lock() {
+      let _ = file.flush();
+      let _ = file.sync_all();
+    }
+  }
+}
+
 pub fn is_dbg_time() -> bool {
   DBGTIME.load(Ordering::Acquire)
 }
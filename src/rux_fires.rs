@@ -1,12 +1,27 @@
-use std::io::{Read, Write};
-use std::process::{Command, Stdio};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use std::any::Any;
+use std::ffi::OsString;
+use std::fs::{File, TryLockError};
+use std::io::{IsTerminal, Read, Write};
+use std::panic::AssertUnwindSafe;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, Once};
 use std::thread;
-use std::time::Duration;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-use crate::rux_debug::{dbg_bleb, dbg_erro};
-use crate::rux_debug::{dbg_call, dbg_lets, dbg_reav, dbg_step};
+use crate::rux_debug;
+use crate::rux_debug::{dbg_bleb, dbg_erro, dbg_info, dbg_warn};
+use crate::rux_debug::{dbg_call, dbg_lets, dbg_reav, dbg_step, dbg_tell};
+use crate::rux_hosts;
 use crate::rux_paths;
-use crate::RubxError;
+use crate::rux_rands;
+use crate::rux_texts;
+use crate::{RubxError, RubxResult};
 
 pub fn cmd(
   command: &str,
@@ -16,13 +31,142 @@ pub fn cmd(
   throw: Option<bool>,
 ) -> Result<(i32, String), RubxError> {
   dbg_call!(command, print, throw);
+  let (result, output, _) =
+    cmd_make(command, args, dir, print, throw, |_| Ok(())).map_err(|err| dbg_bleb!(err))?;
+  Ok((result, output))
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CmdUsage {
+  pub wall: Duration,
+  pub user: Duration,
+  pub system: Duration,
+  pub max_rss: u64,
+  pub signal: Option<i32>,
+}
+
+pub fn cmd_usage(
+  command: &str,
+  args: &[impl AsRef<str>],
+  dir: Option<impl AsRef<str>>,
+  print: Option<bool>,
+  throw: Option<bool>,
+) -> Result<(i32, String, CmdUsage), RubxError> {
+  dbg_call!(command, print, throw);
+  dbg_reav!(cmd_make(command, args, dir, print, throw, |_| Ok(())).map_err(|err| dbg_bleb!(err)))
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CmdUser {
+  pub uid: Option<u32>,
+  pub gid: Option<u32>,
+  pub groups: Option<Vec<u32>>,
+}
+
+impl CmdUser {
+  pub fn of(name: &str) -> Result<CmdUser, RubxError> {
+    dbg_call!(name);
+    let (uid, gid) = get_user_ids(name).map_err(|err| dbg_bleb!(err))?;
+    #[cfg(unix)]
+    let groups = rux_hosts::groups_of(name, gid).map_err(|err| dbg_bleb!(err))?;
+    #[cfg(not(unix))]
+    let groups = vec![gid];
+    dbg_reav!(Ok(CmdUser {
+      uid: Some(uid),
+      gid: Some(gid),
+      groups: Some(groups),
+    }))
+  }
+}
+
+pub fn cmd_as(
+  command: &str,
+  args: &[impl AsRef<str>],
+  dir: Option<impl AsRef<str>>,
+  print: Option<bool>,
+  throw: Option<bool>,
+  user: &CmdUser,
+) -> Result<(i32, String), RubxError> {
+  dbg_call!(command, print, throw, user);
+  let user = user.clone();
+  let (result, output, _) = cmd_make(command, args, dir, print, throw, move |cmd| {
+    cmd_user(cmd, user)
+  })
+  .map_err(|err| dbg_bleb!(err))?;
+  Ok((result, output))
+}
+
+#[cfg(unix)]
+fn cmd_user(cmd: &mut Command, user: CmdUser) -> Result<(), RubxError> {
+  use std::os::unix::process::CommandExt;
+  if cmd_user_is_current(&user) {
+    return Ok(());
+  }
+  let groups = user.groups.as_ref().map(|groups| {
+    groups
+      .iter()
+      .map(|group| *group as libc::gid_t)
+      .collect::<Vec<libc::gid_t>>()
+  });
+  unsafe {
+    cmd.pre_exec(move || {
+      if let Some(groups) = &groups {
+        if libc::setgroups(groups.len() as _, groups.as_ptr()) != 0 {
+          return Err(std::io::Error::last_os_error());
+        }
+      }
+      if let Some(gid) = user.gid {
+        if libc::setgid(gid as libc::gid_t) != 0 {
+          return Err(std::io::Error::last_os_error());
+        }
+      }
+      if let Some(uid) = user.uid {
+        if libc::setuid(uid as libc::uid_t) != 0 {
+          return Err(std::io::Error::last_os_error());
+        }
+      }
+      Ok(())
+    });
+  }
+  Ok(())
+}
+
+#[cfg(unix)]
+fn cmd_user_is_current(user: &CmdUser) -> bool {
+  let (uid, euid) = unsafe { (libc::getuid(), libc::geteuid()) };
+  let (gid, egid) = unsafe { (libc::getgid(), libc::getegid()) };
+  let same_uid = user
+    .uid
+    .is_none_or(|target| target == uid && target == euid);
+  let same_gid = user
+    .gid
+    .is_none_or(|target| target == gid && target == egid);
+  same_uid && same_gid && euid != 0
+}
+
+#[cfg(not(unix))]
+fn cmd_user(_cmd: &mut Command, user: CmdUser) -> Result<(), RubxError> {
+  if user != CmdUser::default() {
+    return Err(dbg_erro!("User switch is only supported on unix", user));
+  }
+  Ok(())
+}
+
+fn cmd_make(
+  command: &str,
+  args: &[impl AsRef<str>],
+  dir: Option<impl AsRef<str>>,
+  print: Option<bool>,
+  throw: Option<bool>,
+  prepare: impl FnOnce(&mut Command) -> Result<(), RubxError>,
+) -> Result<(i32, String, CmdUsage), RubxError> {
   let mut cmd = Command::new(command);
   let args = dbg_lets!(args
     .iter()
     .map(|arg| {
       let arg = arg.as_ref();
       cmd.arg(arg);
-      arg.into()
+      arg
     })
     .collect::<Vec<&str>>());
   let dir: String = if let Some(dir) = dir {
@@ -32,12 +176,16 @@ pub fn cmd(
   };
   dbg_step!(dir);
   cmd.current_dir(&dir);
+  prepare(&mut cmd).map_err(|err| dbg_bleb!(err))?;
+  let started = Instant::now();
   let mut child = cmd
     .stdin(Stdio::null())
     .stdout(Stdio::piped())
     .stderr(Stdio::piped())
     .spawn()
-    .map_err(|err| dbg_erro!(err, command, args, dir))?;
+    .map_err(|err| dbg_bleb!(spawn_erro(err, command), args, dir))?;
+  let pid = child.id();
+  running_add(pid);
   let mut output = String::new();
   child
     .stdout
@@ -58,10 +206,13 @@ pub fn cmd(
   let output = output.trim();
   let output = String::from(output);
   dbg_step!(output);
-  let result = child
-    .wait()
-    .map_err(|err| dbg_erro!(err))?
-    .code()
+  let mut usage = CmdUsage::default();
+  let status = wait_usage(&mut child, &mut usage);
+  running_del(pid);
+  let status = status.map_err(|err| dbg_bleb!(err))?;
+  usage.wall = started.elapsed();
+  dbg_step!(usage);
+  let result = exit_code(status)
     .ok_or("Could not found the exit code")
     .map_err(|err| dbg_erro!(err))?;
   dbg_step!(result);
@@ -73,105 +224,1957 @@ pub fn cmd(
   let throw = if let Some(throw) = throw { throw } else { true };
   dbg_step!(throw);
   if throw && result != 0 {
+    if let Some(signal) = usage.signal {
+      return Err(dbg_erro!(
+        "Command was terminated by a signal",
+        command,
+        result,
+        signal
+      ));
+    }
     return Err(dbg_erro!(
       "Result code from command is different than zero",
       command,
       result
     ));
   }
-  Ok((result, output))
+  Ok((result, output, usage))
 }
 
-pub fn sleep(millis: u64) {
-  dbg_call!(millis);
-  thread::sleep(Duration::from_millis(millis))
+#[cfg(unix)]
+fn wait_usage(child: &mut Child, usage: &mut CmdUsage) -> Result<ExitStatus, RubxError> {
+  use std::os::unix::process::ExitStatusExt;
+  let mut status: libc::c_int = 0;
+  let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+  loop {
+    let result = unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, 0, &mut rusage) };
+    if result >= 0 {
+      break;
+    }
+    let err = std::io::Error::last_os_error();
+    if err.kind() != std::io::ErrorKind::Interrupted {
+      return Err(dbg_erro!(err));
+    }
+  }
+  usage.user = timeval_duration(rusage.ru_utime);
+  usage.system = timeval_duration(rusage.ru_stime);
+  usage.max_rss = if is_mac() {
+    rusage.ru_maxrss as u64
+  } else {
+    rusage.ru_maxrss as u64 * 1024
+  };
+  let status = ExitStatus::from_raw(status);
+  usage.signal = status.signal();
+  Ok(status)
 }
 
-pub fn pause() -> Result<(), RubxError> {
-  dbg_call!();
-  let mut stdin = std::io::stdin();
-  let mut stdout = std::io::stdout();
-  write!(stdout, "Press enter to continue...").map_err(|err| dbg_erro!(err))?;
-  stdout.flush()?;
-  let mut buffer = [0u8];
-  stdin.read(&mut buffer).map_err(|err| dbg_erro!(err))?;
-  Ok(())
+#[cfg(not(unix))]
+fn wait_usage(child: &mut Child, _usage: &mut CmdUsage) -> Result<ExitStatus, RubxError> {
+  child.wait().map_err(|err| dbg_erro!(err))
 }
 
-pub fn exe_path() -> Result<String, RubxError> {
-  dbg_call!();
-  dbg_reav!(Ok(format!(
-    "{}",
-    std::env::current_exe()
-      .map_err(|err| dbg_erro!(err))?
-      .display(),
-  )))
+#[cfg(unix)]
+fn timeval_duration(time: libc::timeval) -> Duration {
+  Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
 }
 
-pub fn exe_dir() -> Result<String, RubxError> {
-  dbg_call!();
-  let exe_path = exe_path().map_err(|err| dbg_bleb!(err))?;
+#[derive(Clone, Debug)]
+pub struct CmdRetry {
+  pub attempts: u32,
+  pub delay: u64,
+  pub max_delay: u64,
+  pub exponential: bool,
+  pub jitter: u32,
+  pub on_codes: Vec<i32>,
+  pub on_output: Option<Regex>,
+}
+
+impl Default for CmdRetry {
+  fn default() -> Self {
+    CmdRetry {
+      attempts: 3,
+      delay: 1000,
+      max_delay: 60000,
+      exponential: true,
+      jitter: 250,
+      on_codes: Vec::new(),
+      on_output: None,
+    }
+  }
+}
+
+impl CmdRetry {
+  pub fn should_retry(&self, code: i32, output: &str) -> bool {
+    dbg_call!(code, output);
+    if let Some(on_output) = &self.on_output {
+      if on_output.is_match(output) {
+        dbg_reav!(true);
+      }
+    }
+    if code == 0 {
+      dbg_reav!(false);
+    }
+    dbg_reav!(
+      (self.on_codes.is_empty() && self.on_output.is_none()) || self.on_codes.contains(&code)
+    )
+  }
+
+  pub fn delay_for(&self, attempt: u32) -> u64 {
+    dbg_call!(attempt);
+    let mut result = self.delay;
+    if self.exponential {
+      let factor = 1u64
+        .checked_shl(attempt.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+      result = result.saturating_mul(factor);
+    }
+    result = result.min(self.max_delay);
+    if self.jitter > 0 {
+      result = result.saturating_add(rux_rands::range(0, self.jitter.saturating_add(1)) as u64);
+    }
+    dbg_reav!(result)
+  }
+}
+
+pub fn cmd_retry(
+  command: &str,
+  args: &[impl AsRef<str>],
+  dir: Option<impl AsRef<str>>,
+  print: Option<bool>,
+  throw: Option<bool>,
+  retry: &CmdRetry,
+) -> Result<(i32, String), RubxError> {
+  dbg_call!(command, print, throw, retry);
+  let dir = dir.map(|dir| String::from(dir.as_ref()));
+  let attempts = retry.attempts.max(1);
+  let mut attempt = 1;
+  let (result, output) = loop {
+    let (result, output) = cmd(command, args, dir.as_ref(), print, Some(false))
+      .map_err(|err| dbg_bleb!(err, attempt))?;
+    dbg_info!("Command attempt finished", command, attempt, result);
+    if attempt >= attempts || !retry.should_retry(result, &output) {
+      break (result, output);
+    }
+    let delay = retry.delay_for(attempt);
+    dbg_warn!("Command attempt will be retried", command, attempt, delay);
+    sleep(delay);
+    attempt += 1;
+  };
+  dbg_step!(result, attempt);
+  let throw = throw.unwrap_or(true);
+  if throw && result != 0 {
+    return Err(dbg_erro!(
+      "Result code from command is different than zero",
+      command,
+      result,
+      attempt
+    ));
+  }
+  Ok((result, output))
+}
+
+pub fn cmd_line(
+  line: &str,
+  expand: Option<bool>,
+  dir: Option<impl AsRef<str>>,
+  print: Option<bool>,
+  throw: Option<bool>,
+) -> Result<(i32, String), RubxError> {
+  dbg_call!(line, expand, print, throw);
+  let words = rux_texts::split_words(line, expand).map_err(|err| dbg_bleb!(err))?;
+  if words.is_empty() {
+    return Err(dbg_erro!("No command was specified", line));
+  }
+  dbg_reav!(cmd(&words[0], &words[1..], dir, print, throw).map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn cmd_shell(
+  line: &str,
+  dir: Option<impl AsRef<str>>,
+  print: Option<bool>,
+  throw: Option<bool>,
+) -> Result<(i32, String), RubxError> {
+  dbg_call!(line, print, throw);
+  let (shell, flag) = if is_win() {
+    ("cmd", "/C")
+  } else {
+    ("sh", "-c")
+  };
+  dbg_reav!(cmd(shell, &[flag, line], dir, print, throw).map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn cmds(
+  commands: Vec<Vec<String>>,
+  dir: Option<impl AsRef<str>>,
+  jobs: Option<usize>,
+  fail_fast: Option<bool>,
+  progress: Option<bool>,
+) -> Result<Vec<RubxResult<(i32, String)>>, RubxError> {
+  dbg_call!(commands, jobs, fail_fast, progress);
+  let total = commands.len();
+  let dir: String = if let Some(dir) = dir {
+    dir.as_ref().into()
+  } else {
+    ".".into()
+  };
+  dbg_step!(dir);
+  let jobs = jobs.unwrap_or_else(num_cpus::get).max(1).min(total.max(1));
+  dbg_step!(jobs);
+  let fail_fast = fail_fast.unwrap_or(false);
+  let progress = progress.unwrap_or(true);
+  let pool = Arc::new(Mutex::new(
+    commands.into_iter().enumerate().rev().collect::<Vec<_>>(),
+  ));
+  let failed = Arc::new(AtomicBool::new(false));
+  let done = Arc::new(AtomicUsize::new(0));
+  let mut handles = Vec::with_capacity(jobs);
+  for _ in 0..jobs {
+    let link_pool = pool.clone();
+    let link_failed = failed.clone();
+    let link_done = done.clone();
+    let link_dir = dir.clone();
+    let handle = thread::spawn(move || {
+      let mut partial = Vec::new();
+      loop {
+        if fail_fast && link_failed.load(Ordering::Acquire) {
+          break;
+        }
+        let next = match link_pool.lock() {
+          Ok(mut lock_pool) => lock_pool.pop(),
+          Err(err) => {
+            dbg_erro!(err);
+            break;
+          }
+        };
+        let (index, command) = match next {
+          Some(next) => next,
+          None => break,
+        };
+        let result = if command.is_empty() {
+          Err(dbg_erro!("No command was specified", index))
+        } else {
+          cmd(
+            &command[0],
+            &command[1..],
+            Some(&link_dir),
+            Some(false),
+            Some(true),
+          )
+          .map_err(|err| dbg_bleb!(err))
+        };
+        if result.is_err() {
+          link_failed.store(true, Ordering::Release);
+        }
+        let finished = link_done.fetch_add(1, Ordering::AcqRel) + 1;
+        if progress {
+          println!(
+            "[{}/{}] {} {}",
+            finished,
+            total,
+            if result.is_ok() { "Done" } else { "Fail" },
+            command.join(" ")
+          );
+        }
+        partial.push((index, result));
+      }
+      partial
+    });
+    handles.push(handle);
+  }
+  let mut results: Vec<Option<RubxResult<(i32, String)>>> = (0..total).map(|_| None).collect();
+  for handle in handles {
+    let partial = match handle.join() {
+      Ok(partial) => partial,
+      Err(error) => return Err(dbg_erro!(format!("{:?}", error))),
+    };
+    for (index, result) in partial {
+      results[index] = Some(result);
+    }
+  }
   dbg_reav!(Ok(
-    rux_paths::path_parent(&exe_path).map_err(|err| dbg_bleb!(err))?,
+    results
+      .into_iter()
+      .map(|result| match result {
+        Some(result) => result,
+        None => Err(dbg_erro!("Command skipped by a previous failure")),
+      })
+      .collect()
   ))
 }
 
-pub fn exe_name() -> Result<String, RubxError> {
-  dbg_call!();
-  let exe_path = exe_path().map_err(|err| dbg_bleb!(err))?;
-  dbg_reav!(Ok(rux_paths::path_name(&exe_path).into()))
+static MANAGED: Lazy<Mutex<Vec<Arc<Mutex<Child>>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+#[cfg(unix)]
+static MANAGED_AT_EXIT: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
+
+pub struct FireChild {
+  child: Arc<Mutex<Child>>,
+  pid: u32,
+  output: Arc<Mutex<String>>,
+  readers: Vec<JoinHandle<()>>,
 }
 
-pub fn exe_stem() -> Result<String, RubxError> {
-  dbg_call!();
-  let exe_path = exe_path().map_err(|err| dbg_bleb!(err))?;
-  dbg_reav!(Ok(rux_paths::path_stem(&exe_path).into()))
+pub fn spawn(
+  command: &str,
+  args: &[impl AsRef<str>],
+  dir: Option<impl AsRef<str>>,
+  print: Option<bool>,
+) -> Result<FireChild, RubxError> {
+  dbg_call!(command, print);
+  let mut cmd = Command::new(command);
+  let args = dbg_lets!(args
+    .iter()
+    .map(|arg| {
+      let arg = arg.as_ref();
+      cmd.arg(arg);
+      arg
+    })
+    .collect::<Vec<&str>>());
+  let dir: String = if let Some(dir) = dir {
+    dir.as_ref().into()
+  } else {
+    ".".into()
+  };
+  dbg_step!(dir);
+  cmd.current_dir(&dir);
+  let mut child = cmd
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|err| dbg_bleb!(spawn_erro(err, command), args, dir))?;
+  let pid = dbg_lets!(child.id());
+  running_add(pid);
+  let print = print.unwrap_or(false);
+  let output = Arc::new(Mutex::new(String::new()));
+  let mut readers = Vec::with_capacity(2);
+  if let Some(stdout) = child.stdout.take() {
+    readers.push(spawn_reader(stdout, output.clone(), print));
+  }
+  if let Some(stderr) = child.stderr.take() {
+    readers.push(spawn_reader(stderr, output.clone(), print));
+  }
+  Ok(FireChild {
+    child: Arc::new(Mutex::new(child)),
+    pid,
+    output,
+    readers,
+  })
 }
 
-pub fn exe_ext() -> &'static str {
-  dbg_call!();
-  dbg_reav!(std::env::consts::EXE_EXTENSION)
+pub fn spawn_managed(
+  command: &str,
+  args: &[impl AsRef<str>],
+  dir: Option<impl AsRef<str>>,
+  print: Option<bool>,
+) -> Result<FireChild, RubxError> {
+  dbg_call!(command, print);
+  let child = spawn(command, args, dir, print).map_err(|err| dbg_bleb!(err))?;
+  let mut managed = MANAGED.lock().map_err(|err| dbg_erro!(err))?;
+  managed.retain(|child| match child.lock() {
+    Ok(mut child) => matches!(child.try_wait(), Ok(None)),
+    Err(_) => false,
+  });
+  managed.push(child.child.clone());
+  Ok(child)
 }
 
-pub fn dot_exe_ext() -> String {
-  dbg_call!();
-  dbg_reav!(if std::env::consts::EXE_EXTENSION.is_empty() {
-    String::default()
+fn managed_del(child: &Arc<Mutex<Child>>) {
+  if let Ok(mut managed) = MANAGED.lock() {
+    managed.retain(|managed| !Arc::ptr_eq(managed, child));
+  }
+}
+
+pub fn spawn_detached(
+  command: &str,
+  args: &[impl AsRef<str>],
+  dir: Option<impl AsRef<str>>,
+  out_path: Option<&str>,
+  err_path: Option<&str>,
+  pid_path: Option<&str>,
+) -> Result<u32, RubxError> {
+  dbg_call!(command, out_path, err_path, pid_path);
+  let mut cmd = Command::new(command);
+  let args = dbg_lets!(args
+    .iter()
+    .map(|arg| {
+      let arg = arg.as_ref();
+      cmd.arg(arg);
+      arg
+    })
+    .collect::<Vec<&str>>());
+  let dir: String = if let Some(dir) = dir {
+    dir.as_ref().into()
   } else {
-    format!(".{}", std::env::consts::EXE_EXTENSION)
+    ".".into()
+  };
+  dbg_step!(dir);
+  cmd.current_dir(&dir);
+  cmd.stdin(Stdio::null());
+  cmd.stdout(detached_stdio(out_path).map_err(|err| dbg_bleb!(err))?);
+  cmd.stderr(detached_stdio(err_path).map_err(|err| dbg_bleb!(err))?);
+  #[cfg(unix)]
+  {
+    use std::os::unix::process::CommandExt;
+    unsafe {
+      cmd.pre_exec(|| {
+        if libc::setsid() < 0 {
+          return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+      });
+    }
+  }
+  #[cfg(windows)]
+  {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x00000008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x00000200;
+    cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+  }
+  let mut child = cmd
+    .spawn()
+    .map_err(|err| dbg_bleb!(spawn_erro(err, command), args, dir))?;
+  let pid = child.id();
+  thread::spawn(move || {
+    let _ = child.wait();
+  });
+  if let Some(pid_path) = pid_path {
+    rux_paths::write_atomic(pid_path, format!("{}\n", pid)).map_err(|err| dbg_bleb!(err))?;
+  }
+  dbg_reav!(Ok(pid))
+}
+
+fn detached_stdio(path: Option<&str>) -> Result<Stdio, RubxError> {
+  Ok(match path {
+    Some(path) => Stdio::from(detached_file(path).map_err(|err| dbg_bleb!(err))?),
+    None => Stdio::null(),
   })
 }
 
-pub fn get_os() -> &'static str {
-  dbg_call!();
-  dbg_reav!(std::env::consts::OS)
+fn detached_file(path: &str) -> Result<File, RubxError> {
+  std::fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(path)
+    .map_err(|err| dbg_erro!(err, path))
 }
 
-pub fn is_lin() -> bool {
-  dbg_call!();
-  dbg_reav!(std::env::consts::OS == "linux")
+pub fn daemonize(
+  dir: Option<&str>,
+  out_path: Option<&str>,
+  err_path: Option<&str>,
+  pid_path: Option<&str>,
+) -> Result<(), RubxError> {
+  dbg_call!(dir, out_path, err_path, pid_path);
+  #[cfg(unix)]
+  {
+    use std::os::unix::io::AsRawFd;
+    #[cfg(target_os = "linux")]
+    {
+      let threads = std::fs::read_dir("/proc/self/task")
+        .map(|tasks| tasks.count())
+        .unwrap_or(1);
+      if threads > 1 {
+        return Err(dbg_erro!(
+          "Daemonize must be called before starting any thread",
+          threads
+        ));
+      }
+    }
+    let null_file = std::fs::OpenOptions::new()
+      .read(true)
+      .write(true)
+      .open("/dev/null")
+      .map_err(|err| dbg_erro!(err))?;
+    let out_file = match out_path {
+      Some(out_path) => detached_file(out_path).map_err(|err| dbg_bleb!(err))?,
+      None => null_file.try_clone().map_err(|err| dbg_erro!(err))?,
+    };
+    let err_file = match err_path {
+      Some(err_path) => detached_file(err_path).map_err(|err| dbg_bleb!(err))?,
+      None => null_file.try_clone().map_err(|err| dbg_erro!(err))?,
+    };
+    let pid_path = match pid_path {
+      Some(pid_path) => Some(rux_paths::path_absolute(pid_path).map_err(|err| dbg_bleb!(err))?),
+      None => None,
+    };
+    rux_debug::archive_flush();
+    std::io::stdout().flush().map_err(|err| dbg_erro!(err))?;
+    daemonize_fork().map_err(|err| dbg_bleb!(err))?;
+    if unsafe { libc::setsid() } < 0 {
+      return Err(dbg_erro!(std::io::Error::last_os_error()));
+    }
+    daemonize_fork().map_err(|err| dbg_bleb!(err))?;
+    if let Some(dir) = dir {
+      rux_paths::cd(dir).map_err(|err| dbg_bleb!(err))?;
+    }
+    for (from, into) in [
+      (null_file.as_raw_fd(), 0),
+      (out_file.as_raw_fd(), 1),
+      (err_file.as_raw_fd(), 2),
+    ] {
+      if unsafe { libc::dup2(from, into) } < 0 {
+        return Err(dbg_erro!(std::io::Error::last_os_error(), from, into));
+      }
+    }
+    if let Some(pid_path) = pid_path {
+      rux_paths::write_atomic(&pid_path, format!("{}\n", std::process::id()))
+        .map_err(|err| dbg_bleb!(err))?;
+    }
+    dbg_info!("Process daemonized", std::process::id());
+    Ok(())
+  }
+  #[cfg(not(unix))]
+  {
+    Err(dbg_erro!("Daemonize is only supported on unix"))
+  }
 }
 
-pub fn is_mac() -> bool {
-  dbg_call!();
-  dbg_reav!(std::env::consts::OS == "macos")
+#[cfg(unix)]
+fn daemonize_fork() -> Result<(), RubxError> {
+  match unsafe { libc::fork() } {
+    -1 => Err(dbg_erro!(std::io::Error::last_os_error())),
+    0 => Ok(()),
+    _ => unsafe { libc::_exit(0) },
+  }
 }
 
-pub fn is_win() -> bool {
-  dbg_call!();
-  dbg_reav!(std::env::consts::OS == "windows")
+fn spawn_reader(
+  mut from: impl Read + Send + 'static,
+  output: Arc<Mutex<String>>,
+  print: bool,
+) -> JoinHandle<()> {
+  thread::spawn(move || {
+    let mut buffer = [0u8; 4096];
+    loop {
+      let size = match from.read(&mut buffer) {
+        Ok(0) => break,
+        Ok(size) => size,
+        Err(err) => {
+          dbg_erro!(err);
+          break;
+        }
+      };
+      let part = String::from_utf8_lossy(&buffer[..size]);
+      if print {
+        print!("{}", part);
+      }
+      if let Ok(mut output) = output.lock() {
+        output.push_str(&part);
+      }
+    }
+  })
 }
 
-pub fn thread_id() -> String {
-  dbg_call!();
-  dbg_reav!(format!("{:?}", thread::current().id()))
+fn exit_code(status: ExitStatus) -> Option<i32> {
+  if let Some(code) = status.code() {
+    return Some(code);
+  }
+  #[cfg(unix)]
+  {
+    use std::os::unix::process::ExitStatusExt;
+    if let Some(signal) = status.signal() {
+      return Some(128 + signal);
+    }
+  }
+  None
 }
 
-pub fn thread_display() -> String {
+impl FireChild {
+  pub fn pid(&self) -> u32 {
+    dbg_call!();
+    dbg_reav!(self.pid)
+  }
+
+  pub fn is_running(&self) -> Result<bool, RubxError> {
+    dbg_call!();
+    dbg_reav!(Ok(self.try_wait().map_err(|err| dbg_bleb!(err))?.is_none()))
+  }
+
+  pub fn try_wait(&self) -> Result<Option<i32>, RubxError> {
+    dbg_call!();
+    let status = self
+      .child
+      .lock()
+      .map_err(|err| dbg_erro!(err))?
+      .try_wait()
+      .map_err(|err| dbg_erro!(err, self.pid))?;
+    let status = match status {
+      Some(status) => status,
+      None => dbg_reav!(Ok(None)),
+    };
+    running_del(self.pid);
+    managed_del(&self.child);
+    dbg_reav!(Ok(Some(
+      exit_code(status)
+        .ok_or("Could not found the exit code")
+        .map_err(|err| dbg_erro!(err, self.pid))?
+    )))
+  }
+
+  pub fn wait(&mut self) -> Result<(i32, String), RubxError> {
+    dbg_call!();
+    let status = child_wait(&self.child, self.pid).map_err(|err| dbg_bleb!(err))?;
+    for reader in self.readers.drain(..) {
+      reader
+        .join()
+        .map_err(|err| dbg_erro!(format!("{:?}", err)))?;
+    }
+    let result = exit_code(status)
+      .ok_or("Could not found the exit code")
+      .map_err(|err| dbg_erro!(err, self.pid))?;
+    dbg_step!(result);
+    let output = self.output().trim().to_string();
+    dbg_reav!(Ok((result, output)))
+  }
+
+  pub fn kill(&self) -> Result<(), RubxError> {
+    dbg_call!();
+    let mut child = self.child.lock().map_err(|err| dbg_erro!(err))?;
+    if child.try_wait().map_err(|err| dbg_erro!(err))?.is_none() {
+      child.kill().map_err(|err| dbg_erro!(err, self.pid))?;
+    }
+    Ok(())
+  }
+
+  pub fn signal(&self, signal: i32) -> Result<(), RubxError> {
+    dbg_call!(signal);
+    let mut child = self.child.lock().map_err(|err| dbg_erro!(err))?;
+    if child.try_wait().map_err(|err| dbg_erro!(err))?.is_none() {
+      signal_pid(self.pid, signal).map_err(|err| dbg_bleb!(err))?;
+    }
+    Ok(())
+  }
+
+  pub fn output(&self) -> String {
+    dbg_call!();
+    dbg_reav!(match self.output.lock() {
+      Ok(output) => output.clone(),
+      Err(err) => err.into_inner().clone(),
+    })
+  }
+}
+
+impl Drop for FireChild {
+  fn drop(&mut self) {
+    let exited = match self.child.lock() {
+      Ok(mut child) => !matches!(child.try_wait(), Ok(None)),
+      Err(_) => true,
+    };
+    if exited {
+      running_del(self.pid);
+      managed_del(&self.child);
+      return;
+    }
+    let child = self.child.clone();
+    let pid = self.pid;
+    let _ = thread::Builder::new()
+      .name(format!("rubx-reaper-{}", pid))
+      .spawn(move || {
+        if let Err(err) = child_wait(&child, pid) {
+          dbg_bleb!(err);
+        }
+      });
+  }
+}
+
+fn child_wait(child: &Arc<Mutex<Child>>, pid: u32) -> Result<ExitStatus, RubxError> {
+  let status = loop {
+    let status = child
+      .lock()
+      .map_err(|err| dbg_erro!(err))?
+      .try_wait()
+      .map_err(|err| dbg_erro!(err, pid))?;
+    if let Some(status) = status {
+      break status;
+    }
+    child_block(child, pid).map_err(|err| dbg_bleb!(err))?;
+  };
+  running_del(pid);
+  managed_del(child);
+  Ok(status)
+}
+
+#[cfg(unix)]
+fn child_block(_: &Arc<Mutex<Child>>, pid: u32) -> Result<(), RubxError> {
+  let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+  loop {
+    let options = libc::WEXITED | libc::WNOWAIT;
+    if unsafe { libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, options) } == 0 {
+      return Ok(());
+    }
+    let err = std::io::Error::last_os_error();
+    match err.raw_os_error() {
+      Some(libc::EINTR) => continue,
+      Some(libc::ECHILD) => return Ok(()),
+      _ => return Err(dbg_erro!(err, pid)),
+    }
+  }
+}
+
+#[cfg(windows)]
+fn child_block(child: &Arc<Mutex<Child>>, pid: u32) -> Result<(), RubxError> {
+  use std::os::windows::io::AsRawHandle;
+  let handle = child.lock().map_err(|err| dbg_erro!(err))?.as_raw_handle();
+  if unsafe { win32::WaitForSingleObject(handle, win32::INFINITE) } == win32::WAIT_FAILED {
+    return Err(dbg_erro!(std::io::Error::last_os_error(), pid));
+  }
+  Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn child_block(_: &Arc<Mutex<Child>>, _: u32) -> Result<(), RubxError> {
+  sleep(10);
+  Ok(())
+}
+
+pub fn managed_kill_all() -> Result<usize, RubxError> {
   dbg_call!();
-  dbg_reav!(if let Some(name) = thread::current().name() {
+  let children = MANAGED
+    .lock()
+    .map_err(|err| dbg_erro!(err))?
+    .drain(..)
+    .collect::<Vec<Arc<Mutex<Child>>>>();
+  let mut killed = 0;
+  let mut failures = Vec::new();
+  let mut remains = Vec::new();
+  for child in children {
+    match managed_kill(&child) {
+      Ok(true) => killed += 1,
+      Ok(false) => {}
+      Err(err) => {
+        failures.push(format!("{}", err));
+        remains.push(child);
+      }
+    }
+  }
+  if !failures.is_empty() {
+    if let Ok(mut managed) = MANAGED.lock() {
+      managed.extend(remains);
+    }
+    return Err(dbg_erro!(
+      format!(
+        "{} managed children could not be killed:\n{}",
+        failures.len(),
+        failures.join("\n")
+      ),
+      killed
+    ));
+  }
+  dbg_reav!(Ok(killed))
+}
+
+fn managed_kill(child: &Arc<Mutex<Child>>) -> Result<bool, RubxError> {
+  let mut child = child.lock().map_err(|err| dbg_erro!(err))?;
+  let pid = child.id();
+  if child
+    .try_wait()
+    .map_err(|err| dbg_erro!(err, pid))?
+    .is_some()
+  {
+    running_del(pid);
+    return Ok(false);
+  }
+  dbg_tell!(pid);
+  child.kill().map_err(|err| dbg_erro!(err, pid))?;
+  child.wait().map_err(|err| dbg_erro!(err, pid))?;
+  running_del(pid);
+  Ok(true)
+}
+
+pub fn managed_kill_at_exit() -> Result<(), RubxError> {
+  dbg_call!();
+  #[cfg(unix)]
+  {
+    extern "C" fn at_exit() {
+      let _ = managed_kill_all();
+    }
+    let mut registered = MANAGED_AT_EXIT.lock().map_err(|err| dbg_erro!(err))?;
+    if !*registered {
+      if unsafe { libc::atexit(at_exit) } != 0 {
+        return Err(dbg_erro!("Could not register the exit handler"));
+      }
+      *registered = true;
+    }
+    Ok(())
+  }
+  #[cfg(not(unix))]
+  {
+    Err(dbg_erro!(
+      "Killing the managed children at exit is only supported on unix"
+    ))
+  }
+}
+
+#[cfg(windows)]
+mod win32 {
+  use std::ffi::c_void;
+
+  pub const INFINITE: u32 = 0xFFFFFFFF;
+  pub const WAIT_OBJECT_0: u32 = 0x00000000;
+  pub const WAIT_TIMEOUT: u32 = 0x00000102;
+  pub const WAIT_FAILED: u32 = 0xFFFFFFFF;
+
+  #[link(name = "kernel32")]
+  extern "system" {
+    pub fn WaitForSingleObject(handle: *mut c_void, millis: u32) -> u32;
+  }
+}
+
+pub fn which(name: &str) -> Result<Option<String>, RubxError> {
+  dbg_call!(name);
+  let mut founds = which_make(name, true).map_err(|err| dbg_bleb!(err))?;
+  dbg_reav!(Ok(founds.pop()))
+}
+
+pub fn which_all(name: &str) -> Result<Vec<String>, RubxError> {
+  dbg_call!(name);
+  dbg_reav!(which_make(name, false).map_err(|err| dbg_bleb!(err)))
+}
+
+fn which_make(name: &str, first: bool) -> Result<Vec<String>, RubxError> {
+  let mut results = Vec::new();
+  if name.is_empty() {
+    return Ok(results);
+  }
+  let exts = which_exts(name);
+  if name.contains('/') || name.contains('\\') {
+    for ext in &exts {
+      let candidate = format!("{}{}", name, ext);
+      if is_executable(&candidate) {
+        results.push(candidate);
+        break;
+      }
+    }
+    return Ok(results);
+  }
+  for dir in which_dirs().map_err(|err| dbg_bleb!(err))? {
+    for ext in &exts {
+      let candidate = rux_paths::path_join(&dir, &format!("{}{}", name, ext))
+        .map_err(|err| dbg_bleb!(err))?;
+      dbg_tell!(candidate);
+      if is_executable(&candidate) {
+        results.push(candidate);
+        if first {
+          return Ok(results);
+        }
+        break;
+      }
+    }
+  }
+  Ok(results)
+}
+
+fn which_dirs() -> Result<Vec<String>, RubxError> {
+  if std::env::var_os("PATH").is_none() {
+    return Ok(Vec::new());
+  }
+  Ok(
+    rux_paths::path_env_dirs()
+      .map_err(|err| dbg_bleb!(err))?
+      .into_iter()
+      .filter(|dir| !dir.is_empty())
+      .collect(),
+  )
+}
+
+fn which_exts(name: &str) -> Vec<String> {
+  let mut results = vec![String::default()];
+  if is_win() {
+    let path_ext = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".into());
+    let exts = path_ext
+      .split(';')
+      .filter(|ext| !ext.is_empty())
+      .map(|ext| ext.to_lowercase())
+      .collect::<Vec<String>>();
+    if !rux_paths::path_ext_is_on(name, &exts) {
+      results.extend(exts);
+    }
+  }
+  results
+}
+
+pub fn is_executable(path: &str) -> bool {
+  dbg_call!(path);
+  let metadata = match std::fs::metadata(path) {
+    Ok(metadata) => metadata,
+    Err(_) => dbg_reav!(false),
+  };
+  if !metadata.is_file() {
+    dbg_reav!(false);
+  }
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    dbg_reav!(metadata.permissions().mode() & 0o111 != 0)
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(true)
+  }
+}
+
+fn spawn_erro(err: std::io::Error, command: &str) -> RubxError {
+  if err.kind() == std::io::ErrorKind::NotFound
+    && !command.contains('/')
+    && !command.contains('\\')
+    && matches!(which(command), Ok(None))
+  {
+    let searched = which_dirs().unwrap_or_default();
+    return dbg_erro!("Program not found", command, searched);
+  }
+  dbg_erro!(err, command)
+}
+
+pub fn sleep(millis: u64) {
+  dbg_call!(millis);
+  thread::sleep(Duration::from_millis(millis))
+}
+
+pub fn pause() -> Result<(), RubxError> {
+  dbg_call!();
+  if !is_interactive() {
+    return Ok(());
+  }
+  let mut stdin = std::io::stdin();
+  let mut stdout = std::io::stdout();
+  write!(stdout, "Press enter to continue...").map_err(|err| dbg_erro!(err))?;
+  stdout.flush()?;
+  let mut buffer = [0u8];
+  stdin.read(&mut buffer).map_err(|err| dbg_erro!(err))?;
+  Ok(())
+}
+
+pub fn pause_for(seconds: Option<u64>) -> Result<(), RubxError> {
+  dbg_call!(seconds);
+  if !is_interactive() {
+    return Ok(());
+  }
+  if wait_input("Press enter to continue...", seconds).map_err(|err| dbg_bleb!(err))? {
+    stdin_line().map_err(|err| dbg_bleb!(err))?;
+  }
+  Ok(())
+}
+
+pub fn pause_key(seconds: Option<u64>) -> Result<Option<char>, RubxError> {
+  dbg_call!(seconds);
+  if !is_interactive() {
+    return Ok(None);
+  }
+  #[cfg(unix)]
+  {
+    let _raw_mode = RawMode::enter().map_err(|err| dbg_bleb!(err))?;
+    let ready =
+      wait_input("Press any key to continue...", seconds).map_err(|err| dbg_bleb!(err))?;
+    let mut result = None;
+    if ready {
+      let mut buffer = [0u8];
+      let size = unsafe { libc::read(0, buffer.as_mut_ptr() as *mut libc::c_void, 1) };
+      if size == 1 {
+        result = Some(buffer[0] as char);
+      }
+    }
+    println!();
+    dbg_reav!(Ok(result))
+  }
+  #[cfg(not(unix))]
+  {
+    pause_for(seconds).map_err(|err| dbg_bleb!(err))?;
+    dbg_reav!(Ok(None))
+  }
+}
+
+pub fn prompt(
+  message: &str,
+  default: Option<&str>,
+  seconds: Option<u64>,
+) -> Result<String, RubxError> {
+  dbg_call!(message, default, seconds);
+  let default = String::from(default.unwrap_or_default());
+  if !is_interactive() {
+    dbg_reav!(Ok(default));
+  }
+  let message = if default.is_empty() {
+    String::from(message)
+  } else {
+    format!("{} [{}]", message, default)
+  };
+  if !wait_input(&message, seconds).map_err(|err| dbg_bleb!(err))? {
+    dbg_reav!(Ok(default));
+  }
+  let buffer = stdin_line().map_err(|err| dbg_bleb!(err))?;
+  let result = buffer.trim();
+  dbg_reav!(Ok(if result.is_empty() {
+    default
+  } else {
+    String::from(result)
+  }))
+}
+
+pub fn is_interactive() -> bool {
+  dbg_call!();
+  if let Ok(ci) = std::env::var("CI") {
+    if rux_texts::is_truthy(&ci) {
+      dbg_reav!(false);
+    }
+  }
+  dbg_reav!(std::io::stdin().is_terminal())
+}
+
+fn wait_input(message: &str, seconds: Option<u64>) -> Result<bool, RubxError> {
+  let mut stdout = std::io::stdout();
+  let seconds = match seconds {
+    Some(seconds) => seconds,
+    None => {
+      write!(stdout, "{} ", message).map_err(|err| dbg_erro!(err))?;
+      stdout.flush().map_err(|err| dbg_erro!(err))?;
+      return Ok(true);
+    }
+  };
+  for remaining in (1..=seconds).rev() {
+    write!(stdout, "\r{} ({}s) ", message, remaining).map_err(|err| dbg_erro!(err))?;
+    stdout.flush().map_err(|err| dbg_erro!(err))?;
+    if stdin_ready(1000).map_err(|err| dbg_bleb!(err))? {
+      return Ok(true);
+    }
+  }
+  writeln!(stdout).map_err(|err| dbg_erro!(err))?;
+  Ok(false)
+}
+
+#[cfg(unix)]
+fn stdin_ready(millis: i32) -> Result<bool, RubxError> {
+  let mut poll_fd = libc::pollfd {
+    fd: 0,
+    events: libc::POLLIN,
+    revents: 0,
+  };
+  let result = unsafe { libc::poll(&mut poll_fd, 1, millis) };
+  if result < 0 {
+    let err = std::io::Error::last_os_error();
+    if err.kind() == std::io::ErrorKind::Interrupted {
+      return Ok(false);
+    }
+    return Err(dbg_erro!(err));
+  }
+  Ok(result > 0)
+}
+
+#[cfg(windows)]
+fn stdin_ready(millis: i32) -> Result<bool, RubxError> {
+  use std::os::windows::io::AsRawHandle;
+  let handle = std::io::stdin().as_raw_handle();
+  match unsafe { win32::WaitForSingleObject(handle, millis.max(0) as u32) } {
+    win32::WAIT_OBJECT_0 => Ok(true),
+    win32::WAIT_TIMEOUT => Ok(false),
+    _ => Err(dbg_erro!(std::io::Error::last_os_error())),
+  }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn stdin_ready(_: i32) -> Result<bool, RubxError> {
+  Ok(true)
+}
+
+fn stdin_line() -> Result<String, RubxError> {
+  let mut buffer = String::new();
+  std::io::stdin()
+    .read_line(&mut buffer)
+    .map_err(|err| dbg_erro!(err))?;
+  Ok(buffer)
+}
+
+#[cfg(unix)]
+struct RawMode {
+  original: libc::termios,
+}
+
+#[cfg(unix)]
+impl RawMode {
+  fn enter() -> Result<RawMode, RubxError> {
+    let mut original: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(0, &mut original) } != 0 {
+      return Err(dbg_erro!(std::io::Error::last_os_error()));
+    }
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 1;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(0, libc::TCSANOW, &raw) } != 0 {
+      return Err(dbg_erro!(std::io::Error::last_os_error()));
+    }
+    Ok(RawMode { original })
+  }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+  fn drop(&mut self) {
+    unsafe {
+      libc::tcsetattr(0, libc::TCSANOW, &self.original);
+    }
+  }
+}
+
+pub fn exe_path() -> Result<String, RubxError> {
+  dbg_call!();
+  dbg_reav!(Ok(format!(
+    "{}",
+    std::env::current_exe()
+      .map_err(|err| dbg_erro!(err))?
+      .display(),
+  )))
+}
+
+pub fn exe_dir() -> Result<String, RubxError> {
+  dbg_call!();
+  let exe_path = exe_path().map_err(|err| dbg_bleb!(err))?;
+  dbg_reav!(Ok(
+    rux_paths::path_parent(&exe_path).map_err(|err| dbg_bleb!(err))?,
+  ))
+}
+
+pub fn exe_name() -> Result<String, RubxError> {
+  dbg_call!();
+  let exe_path = exe_path().map_err(|err| dbg_bleb!(err))?;
+  dbg_reav!(Ok(rux_paths::path_name(&exe_path).into()))
+}
+
+pub fn exe_stem() -> Result<String, RubxError> {
+  dbg_call!();
+  let exe_path = exe_path().map_err(|err| dbg_bleb!(err))?;
+  dbg_reav!(Ok(rux_paths::path_stem(&exe_path).into()))
+}
+
+pub fn exe_ext() -> &'static str {
+  dbg_call!();
+  dbg_reav!(std::env::consts::EXE_EXTENSION)
+}
+
+pub fn dot_exe_ext() -> String {
+  dbg_call!();
+  dbg_reav!(if std::env::consts::EXE_EXTENSION.is_empty() {
+    String::default()
+  } else {
+    format!(".{}", std::env::consts::EXE_EXTENSION)
+  })
+}
+
+pub fn get_os() -> &'static str {
+  dbg_call!();
+  dbg_reav!(std::env::consts::OS)
+}
+
+pub fn is_lin() -> bool {
+  dbg_call!();
+  dbg_reav!(std::env::consts::OS == "linux")
+}
+
+pub fn is_mac() -> bool {
+  dbg_call!();
+  dbg_reav!(std::env::consts::OS == "macos")
+}
+
+pub fn is_win() -> bool {
+  dbg_call!();
+  dbg_reav!(std::env::consts::OS == "windows")
+}
+
+pub fn get_uid() -> Result<u32, RubxError> {
+  dbg_call!();
+  dbg_reav!(rux_hosts::get_user_id().map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn get_euid() -> Result<u32, RubxError> {
+  dbg_call!();
+  dbg_reav!(rux_hosts::get_user_euid().map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn get_gid() -> Result<u32, RubxError> {
+  dbg_call!();
+  dbg_reav!(rux_hosts::get_group_id().map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn get_egid() -> Result<u32, RubxError> {
+  dbg_call!();
+  dbg_reav!(rux_hosts::get_group_egid().map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn get_username() -> Result<String, RubxError> {
+  dbg_call!();
+  dbg_reav!(rux_hosts::get_user_name().map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn get_user_ids(name: &str) -> Result<(u32, u32), RubxError> {
+  dbg_call!(name);
+  #[cfg(unix)]
+  {
+    dbg_reav!(rux_hosts::passwd_named(name).map_err(|err| dbg_bleb!(err)))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(Err(dbg_erro!("User ids are only supported on unix", name)))
+  }
+}
+
+pub fn is_elevated() -> bool {
+  dbg_call!();
+  #[cfg(unix)]
+  {
+    dbg_reav!(unsafe { libc::geteuid() } == 0)
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(matches!(
+      cmd("net", &["session"], None::<&str>, Some(false), Some(false)),
+      Ok((0, _))
+    ))
+  }
+}
+
+pub fn thread_id() -> String {
+  dbg_call!();
+  dbg_reav!(format!("{:?}", thread::current().id()))
+}
+
+pub fn thread_display() -> String {
+  dbg_call!();
+  dbg_reav!(if let Some(name) = thread::current().name() {
     format!("{}", name)
   } else {
     format!("{}", thread_id())
   })
 }
+
+pub fn env_get(name: &str) -> Option<String> {
+  dbg_call!(name);
+  dbg_reav!(std::env::var(name).ok())
+}
+
+pub fn env_get_or(name: &str, default: &str) -> String {
+  dbg_call!(name, default);
+  dbg_reav!(std::env::var(name).unwrap_or_else(|_| String::from(default)))
+}
+
+pub fn env_get_bool(name: &str) -> bool {
+  dbg_call!(name);
+  dbg_reav!(match std::env::var(name) {
+    Ok(value) => rux_texts::is_truthy(value.trim()),
+    Err(_) => false,
+  })
+}
+
+pub fn env_get_int(name: &str) -> Result<Option<i64>, RubxError> {
+  dbg_call!(name);
+  dbg_reav!(match std::env::var(name) {
+    Ok(value) => Ok(Some(
+      value
+        .trim()
+        .parse::<i64>()
+        .map_err(|err| dbg_erro!(err, name, value))?
+    )),
+    Err(_) => Ok(None),
+  })
+}
+
+pub fn env_get_list(name: &str) -> Vec<String> {
+  dbg_call!(name);
+  dbg_reav!(match std::env::var(name) {
+    Ok(value) => value
+      .split(env_list_sep())
+      .filter(|item| !item.is_empty())
+      .map(String::from)
+      .collect(),
+    Err(_) => Vec::new(),
+  })
+}
+
+pub fn env_list_sep() -> char {
+  dbg_call!();
+  dbg_reav!(if is_win() { ';' } else { ':' })
+}
+
+pub fn env_set(name: &str, value: &str) {
+  dbg_call!(name, value);
+  std::env::set_var(name, value);
+}
+
+pub fn env_set_list(name: &str, values: &[impl AsRef<str> + std::fmt::Debug]) {
+  dbg_call!(name, values);
+  let value = values
+    .iter()
+    .map(|item| item.as_ref())
+    .collect::<Vec<&str>>()
+    .join(&env_list_sep().to_string());
+  std::env::set_var(name, value);
+}
+
+pub fn env_unset(name: &str) {
+  dbg_call!(name);
+  std::env::remove_var(name);
+}
+
+pub fn env_expand(text: &str) -> Result<String, RubxError> {
+  dbg_call!(text);
+  dbg_reav!(rux_texts::split_words_expand_all(text).map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn env_load(path: &str, overwrite: Option<bool>) -> Result<usize, RubxError> {
+  dbg_call!(path, overwrite);
+  dbg_reav!(env_load_make(path, overwrite, None).map_err(|err| dbg_bleb!(err)))
+}
+
+fn env_load_make(
+  path: &str,
+  overwrite: Option<bool>,
+  mut guard: Option<&mut EnvGuard>,
+) -> Result<usize, RubxError> {
+  let overwrite = overwrite.unwrap_or(false);
+  let contents = rux_texts::read(path).map_err(|err| dbg_bleb!(err))?;
+  let mut loaded = 0;
+  for line in contents.lines() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let equals_pos = line
+      .find('=')
+      .ok_or("Could not find the equals sign")
+      .map_err(|err| dbg_erro!(err, path, line))?;
+    let name = line[0..equals_pos].trim();
+    let value = line[equals_pos + 1..].trim();
+    let value = if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+      String::from(&value[1..value.len() - 1])
+    } else if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+      env_expand(
+        &value[1..value.len() - 1]
+          .replace("\\n", "\n")
+          .replace("\\\"", "\""),
+      )
+      .map_err(|err| dbg_bleb!(err))?
+    } else {
+      let value = match value.find(" #") {
+        Some(pos) => value[..pos].trim_end(),
+        None => value,
+      };
+      env_expand(value).map_err(|err| dbg_bleb!(err))?
+    };
+    dbg_tell!(name, value);
+    if overwrite || std::env::var_os(name).is_none() {
+      if let Some(guard) = guard.as_mut() {
+        guard.save(name);
+      }
+      std::env::set_var(name, value);
+      loaded += 1;
+    }
+  }
+  dbg_reav!(Ok(loaded))
+}
+
+pub struct EnvGuard {
+  saved: Vec<(String, Option<OsString>)>,
+}
+
+pub fn env_guard() -> EnvGuard {
+  dbg_call!();
+  EnvGuard { saved: Vec::new() }
+}
+
+impl EnvGuard {
+  pub fn set(&mut self, name: &str, value: &str) {
+    dbg_call!(name, value);
+    self.save(name);
+    env_set(name, value);
+  }
+
+  pub fn set_list(&mut self, name: &str, values: &[impl AsRef<str> + std::fmt::Debug]) {
+    dbg_call!(name, values);
+    self.save(name);
+    env_set_list(name, values);
+  }
+
+  pub fn unset(&mut self, name: &str) {
+    dbg_call!(name);
+    self.save(name);
+    env_unset(name);
+  }
+
+  pub fn load(&mut self, path: &str, overwrite: Option<bool>) -> Result<usize, RubxError> {
+    dbg_call!(path, overwrite);
+    dbg_reav!(env_load_make(path, overwrite, Some(self)).map_err(|err| dbg_bleb!(err)))
+  }
+
+  fn save(&mut self, name: &str) {
+    if !self.saved.iter().any(|(saved, _)| saved == name) {
+      self
+        .saved
+        .push((String::from(name), std::env::var_os(name)));
+    }
+  }
+}
+
+impl Drop for EnvGuard {
+  fn drop(&mut self) {
+    for (name, value) in self.saved.drain(..).rev() {
+      match value {
+        Some(value) => std::env::set_var(&name, value),
+        None => std::env::remove_var(&name),
+      }
+    }
+  }
+}
+
+#[cfg(unix)]
+pub const SIGHUP: i32 = libc::SIGHUP;
+#[cfg(unix)]
+pub const SIGINT: i32 = libc::SIGINT;
+#[cfg(unix)]
+pub const SIGKILL: i32 = libc::SIGKILL;
+#[cfg(unix)]
+pub const SIGTERM: i32 = libc::SIGTERM;
+#[cfg(not(unix))]
+pub const SIGHUP: i32 = 1;
+#[cfg(not(unix))]
+pub const SIGINT: i32 = 2;
+#[cfg(not(unix))]
+pub const SIGKILL: i32 = 9;
+#[cfg(not(unix))]
+pub const SIGTERM: i32 = 15;
+
+pub static SHUTDOWN_SIGNALS: &[i32] = &[SIGINT, SIGTERM, SIGHUP];
+
+type SignalHandler = Arc<dyn Fn(i32) + Send + Sync>;
+
+static RUNNING: Lazy<Mutex<Vec<u32>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+static SHUTDOWN_SIGNAL: AtomicI32 = AtomicI32::new(0);
+static SHUTDOWN_CLEANS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static SIGNAL_FORWARD: AtomicBool = AtomicBool::new(true);
+static SIGNAL_HANDLERS: Lazy<Mutex<Vec<(i32, SignalHandler)>>> =
+  Lazy::new(|| Mutex::new(Vec::new()));
+#[cfg(unix)]
+static SIGNAL_PIPE: AtomicI32 = AtomicI32::new(-1);
+#[cfg(unix)]
+static SIGNAL_SETUP: Once = Once::new();
+
+fn running_add(pid: u32) {
+  if let Ok(mut running) = RUNNING.lock() {
+    running.push(pid);
+  }
+}
+
+fn running_del(pid: u32) {
+  if let Ok(mut running) = RUNNING.lock() {
+    running.retain(|item| *item != pid);
+  }
+}
+
+pub fn running_pids() -> Vec<u32> {
+  dbg_call!();
+  dbg_reav!(match RUNNING.lock() {
+    Ok(running) => running.clone(),
+    Err(err) => err.into_inner().clone(),
+  })
+}
+
+pub fn running_signal_all(signal: i32) -> Result<usize, RubxError> {
+  dbg_call!(signal);
+  let mut signaled = 0;
+  for pid in running_pids() {
+    dbg_tell!(pid);
+    signal_pid(pid, signal).map_err(|err| dbg_bleb!(err))?;
+    signaled += 1;
+  }
+  dbg_reav!(Ok(signaled))
+}
+
+pub fn signal_pid(pid: u32, signal: i32) -> Result<(), RubxError> {
+  dbg_call!(pid, signal);
+  #[cfg(unix)]
+  {
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } != 0 {
+      return Err(dbg_erro!(std::io::Error::last_os_error(), pid, signal));
+    }
+    Ok(())
+  }
+  #[cfg(not(unix))]
+  {
+    Err(dbg_erro!("Signals are only supported on unix", pid, signal))
+  }
+}
+
+pub fn is_shutdown() -> bool {
+  SHUTDOWN.load(Ordering::Acquire)
+}
+
+pub fn put_shutdown() {
+  dbg_call!();
+  SHUTDOWN.store(true, Ordering::Release);
+}
+
+pub fn get_shutdown_signal() -> Option<i32> {
+  dbg_call!();
+  let signal = SHUTDOWN_SIGNAL.load(Ordering::Acquire);
+  dbg_reav!(if signal == 0 { None } else { Some(signal) })
+}
+
+pub fn set_signal_forward(forward: bool) {
+  dbg_call!(forward);
+  SIGNAL_FORWARD.store(forward, Ordering::Release);
+}
+
+pub fn get_signal_forward() -> bool {
+  dbg_call!();
+  dbg_reav!(SIGNAL_FORWARD.load(Ordering::Acquire))
+}
+
+pub fn shutdown_clean(path: &str) -> Result<(), RubxError> {
+  dbg_call!(path);
+  SHUTDOWN_CLEANS
+    .lock()
+    .map_err(|err| dbg_erro!(err))?
+    .push(String::from(path));
+  Ok(())
+}
+
+pub fn on_shutdown(handler: impl Fn(i32) + Send + Sync + 'static) -> Result<(), RubxError> {
+  dbg_call!();
+  let handler: SignalHandler = Arc::new(handler);
+  for signal in SHUTDOWN_SIGNALS {
+    on_signal_make(*signal, handler.clone()).map_err(|err| dbg_bleb!(err))?;
+  }
+  Ok(())
+}
+
+pub fn on_signal(
+  signal: i32,
+  handler: impl Fn(i32) + Send + Sync + 'static,
+) -> Result<(), RubxError> {
+  dbg_call!(signal);
+  on_signal_make(signal, Arc::new(handler)).map_err(|err| dbg_bleb!(err))
+}
+
+fn on_signal_make(signal: i32, handler: SignalHandler) -> Result<(), RubxError> {
+  signal_install(signal).map_err(|err| dbg_bleb!(err))?;
+  SIGNAL_HANDLERS
+    .lock()
+    .map_err(|err| dbg_erro!(err))?
+    .push((signal, handler));
+  Ok(())
+}
+
+#[cfg(unix)]
+fn signal_install(signal: i32) -> Result<(), RubxError> {
+  let mut setup_err = None;
+  SIGNAL_SETUP.call_once(|| {
+    let mut fds = [0 as libc::c_int; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+      setup_err = Some(dbg_erro!(std::io::Error::last_os_error()));
+      return;
+    }
+    SIGNAL_PIPE.store(fds[1], Ordering::Release);
+    let reader = fds[0];
+    let watcher = thread::Builder::new()
+      .name("rubx-signals".into())
+      .spawn(move || signal_watch(reader));
+    if let Err(err) = watcher {
+      setup_err = Some(dbg_erro!(err));
+    }
+  });
+  if let Some(err) = setup_err {
+    return Err(err);
+  }
+  if SIGNAL_PIPE.load(Ordering::Acquire) < 0 {
+    return Err(dbg_erro!("The signal watcher is not running", signal));
+  }
+  let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+  action.sa_sigaction = signal_catch as extern "C" fn(libc::c_int) as libc::sighandler_t;
+  action.sa_flags = libc::SA_RESTART;
+  unsafe { libc::sigemptyset(&mut action.sa_mask) };
+  if unsafe { libc::sigaction(signal, &action, std::ptr::null_mut()) } != 0 {
+    return Err(dbg_erro!(std::io::Error::last_os_error(), signal));
+  }
+  Ok(())
+}
+
+#[cfg(not(unix))]
+fn signal_install(signal: i32) -> Result<(), RubxError> {
+  Err(dbg_erro!("Signals are only supported on unix", signal))
+}
+
+#[cfg(unix)]
+extern "C" fn signal_catch(signal: libc::c_int) {
+  if SHUTDOWN_SIGNALS.contains(&signal) {
+    if SHUTDOWN_SIGNAL.load(Ordering::Acquire) != 0 {
+      unsafe { libc::_exit(128 + signal) };
+    }
+    SHUTDOWN_SIGNAL.store(signal, Ordering::Release);
+    SHUTDOWN.store(true, Ordering::Release);
+  }
+  let pipe = SIGNAL_PIPE.load(Ordering::Acquire);
+  if pipe >= 0 {
+    let byte = signal as u8;
+    unsafe { libc::write(pipe, &byte as *const u8 as *const libc::c_void, 1) };
+  }
+}
+
+#[cfg(unix)]
+fn signal_watch(reader: libc::c_int) {
+  loop {
+    let mut byte = 0u8;
+    let size = unsafe { libc::read(reader, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+    if size < 0 {
+      if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+        continue;
+      }
+      break;
+    }
+    if size == 0 {
+      break;
+    }
+    let signal = byte as i32;
+    dbg_info!("Signal received", signal);
+    if SIGNAL_FORWARD.load(Ordering::Acquire) {
+      let _ = running_signal_all(signal);
+    }
+    let handlers = match SIGNAL_HANDLERS.lock() {
+      Ok(handlers) => handlers
+        .iter()
+        .filter(|(on, _)| *on == signal)
+        .map(|(_, handler)| handler.clone())
+        .collect::<Vec<SignalHandler>>(),
+      Err(_) => Vec::new(),
+    };
+    for handler in handlers {
+      handler(signal);
+    }
+    if SHUTDOWN_SIGNALS.contains(&signal) {
+      if let Ok(cleans) = SHUTDOWN_CLEANS.lock() {
+        for path in cleans.iter() {
+          let _ = rux_paths::rm(path);
+        }
+      }
+      rux_debug::archive_flush();
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct FireLock {
+  file: File,
+  lock_path: String,
+  pid_path: String,
+}
+
+pub fn lock(name: &str, dir: Option<&str>) -> Result<FireLock, RubxError> {
+  dbg_call!(name, dir);
+  match try_lock(name, dir).map_err(|err| dbg_bleb!(err))? {
+    Some(lock) => Ok(lock),
+    None => {
+      let owner = lock_owner(name, dir).map_err(|err| dbg_bleb!(err))?;
+      Err(dbg_erro!("The lock is already held", name, owner))
+    }
+  }
+}
+
+pub fn try_lock(name: &str, dir: Option<&str>) -> Result<Option<FireLock>, RubxError> {
+  dbg_call!(name, dir);
+  let (lock_path, pid_path) = lock_paths(name, dir).map_err(|err| dbg_bleb!(err))?;
+  dbg_step!(lock_path, pid_path);
+  let file = std::fs::OpenOptions::new()
+    .create(true)
+    .truncate(false)
+    .write(true)
+    .open(&lock_path)
+    .map_err(|err| dbg_erro!(err, lock_path))?;
+  match file.try_lock() {
+    Ok(()) => {}
+    Err(TryLockError::WouldBlock) => dbg_reav!(Ok(None)),
+    Err(TryLockError::Error(err)) => return Err(dbg_erro!(err, lock_path)),
+  }
+  if let Some(stale) = read_pid(&pid_path) {
+    if stale != std::process::id() {
+      dbg_warn!("Replacing a stale pid file", pid_path, stale);
+    }
+  }
+  rux_paths::write_atomic(&pid_path, format!("{}\n", std::process::id()))
+    .map_err(|err| dbg_bleb!(err))?;
+  dbg_reav!(Ok(Some(FireLock {
+    file,
+    lock_path,
+    pid_path,
+  })))
+}
+
+pub fn lock_owner(name: &str, dir: Option<&str>) -> Result<Option<u32>, RubxError> {
+  dbg_call!(name, dir);
+  let (_, pid_path) = lock_paths(name, dir).map_err(|err| dbg_bleb!(err))?;
+  dbg_reav!(Ok(read_pid(&pid_path).filter(|pid| is_alive(*pid))))
+}
+
+pub fn runtime_dir() -> String {
+  dbg_call!();
+  if let Ok(runtime) = std::env::var("XDG_RUNTIME_DIR") {
+    if !runtime.is_empty() && rux_paths::is_dir(&runtime) {
+      dbg_reav!(runtime);
+    }
+  }
+  dbg_reav!(format!("{}", std::env::temp_dir().display()))
+}
+
+pub fn is_alive(pid: u32) -> bool {
+  dbg_call!(pid);
+  #[cfg(unix)]
+  {
+    if unsafe { libc::kill(pid as libc::pid_t, 0) } == 0 {
+      dbg_reav!(true);
+    }
+    dbg_reav!(std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(pid == std::process::id())
+  }
+}
+
+fn lock_paths(name: &str, dir: Option<&str>) -> Result<(String, String), RubxError> {
+  let dir = match dir {
+    Some(dir) => String::from(dir),
+    None => exe_dir().map_err(|err| dbg_bleb!(err))?,
+  };
+  let lock_path =
+    rux_paths::path_join(&dir, &format!("{}.lock", name)).map_err(|err| dbg_bleb!(err))?;
+  let pid_path =
+    rux_paths::path_join(&dir, &format!("{}.pid", name)).map_err(|err| dbg_bleb!(err))?;
+  Ok((lock_path, pid_path))
+}
+
+fn read_pid(pid_path: &str) -> Option<u32> {
+  rux_texts::read(pid_path).ok()?.trim().parse::<u32>().ok()
+}
+
+impl FireLock {
+  pub fn lock_path(&self) -> &str {
+    &self.lock_path
+  }
+
+  pub fn pid_path(&self) -> &str {
+    &self.pid_path
+  }
+}
+
+impl Drop for FireLock {
+  fn drop(&mut self) {
+    let _ = rux_paths::rm(&self.pid_path);
+    let _ = self.file.unlock();
+  }
+}
+
+type FireJob = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Debug)]
+pub struct FirePool {
+  sender: Option<Sender<FireJob>>,
+  workers: Vec<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+pub struct FireTask<T> {
+  receiver: Receiver<Result<T, RubxError>>,
+}
+
+pub fn pool(size: Option<usize>) -> Result<FirePool, RubxError> {
+  dbg_call!(size);
+  let size = size.unwrap_or_else(num_cpus::get).max(1);
+  let (sender, receiver) = mpsc::channel::<FireJob>();
+  let receiver = Arc::new(Mutex::new(receiver));
+  let mut workers = Vec::with_capacity(size);
+  for index in 0..size {
+    let link_receiver = receiver.clone();
+    let worker = thread::Builder::new()
+      .name(format!("rubx-worker-{}", index))
+      .spawn(move || loop {
+        let job = match link_receiver.lock() {
+          Ok(receiver) => receiver.recv(),
+          Err(err) => {
+            dbg_erro!(err);
+            break;
+          }
+        };
+        match job {
+          Ok(job) => {
+            if let Err(err) = catch_panic(job) {
+              dbg_bleb!(err);
+            }
+          }
+          Err(_) => break,
+        }
+      })
+      .map_err(|err| dbg_erro!(err, index))?;
+    workers.push(worker);
+  }
+  dbg_reav!(Ok(FirePool {
+    sender: Some(sender),
+    workers,
+  }))
+}
+
+impl FirePool {
+  pub fn size(&self) -> usize {
+    dbg_call!();
+    dbg_reav!(self.workers.len())
+  }
+
+  pub fn execute(&self, job: impl FnOnce() + Send + 'static) -> Result<(), RubxError> {
+    dbg_call!();
+    self
+      .execute_job(Box::new(job))
+      .map_err(|err| dbg_bleb!(err))
+  }
+
+  fn execute_job(&self, job: FireJob) -> Result<(), RubxError> {
+    self
+      .sender
+      .as_ref()
+      .ok_or("The pool was already shutdown")
+      .map_err(|err| dbg_erro!(err))?
+      .send(job)
+      .map_err(|err| dbg_erro!(err))?;
+    Ok(())
+  }
+
+  pub fn submit<T: Send + 'static>(
+    &self,
+    job: impl FnOnce() -> T + Send + 'static,
+  ) -> Result<FireTask<T>, RubxError> {
+    dbg_call!();
+    let (sender, receiver) = mpsc::channel();
+    self
+      .execute(move || {
+        let _ = sender.send(catch_panic(job));
+      })
+      .map_err(|err| dbg_bleb!(err))?;
+    Ok(FireTask { receiver })
+  }
+
+  pub fn map<T, R, F>(&self, items: Vec<T>, job: F) -> Result<Vec<R>, RubxError>
+  where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+  {
+    dbg_call!();
+    let count = items.len();
+    let job = &job;
+    let (sender, receiver) = mpsc::channel::<(usize, Result<R, RubxError>)>();
+    let mut sent_err = None;
+    for (index, item) in items.into_iter().enumerate() {
+      let sender = sender.clone();
+      let scoped: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+        let _ = sender.send((index, catch_panic(|| job(item))));
+      });
+      // Every job is drained below before returning, so none outlives this call.
+      let scoped =
+        unsafe { std::mem::transmute::<Box<dyn FnOnce() + Send + '_>, FireJob>(scoped) };
+      if let Err(err) = self.execute_job(scoped) {
+        sent_err = Some(err);
+        break;
+      }
+    }
+    drop(sender);
+    let mut slots = (0..count)
+      .map(|_| None)
+      .collect::<Vec<Option<Result<R, RubxError>>>>();
+    for (index, result) in receiver {
+      slots[index] = Some(result);
+    }
+    if let Some(err) = sent_err {
+      return Err(dbg_bleb!(err));
+    }
+    let mut results = Vec::with_capacity(count);
+    let mut failures = Vec::new();
+    for (index, slot) in slots.into_iter().enumerate() {
+      match slot {
+        Some(Ok(result)) => results.push(result),
+        Some(Err(err)) => failures.push(format!("[{}] {}", index, err)),
+        None => failures.push(format!("[{}] The job was not executed", index)),
+      }
+    }
+    if !failures.is_empty() {
+      return Err(dbg_erro!(
+        format!(
+          "{} of {} jobs failed:\n{}",
+          failures.len(),
+          count,
+          failures.join("\n")
+        ),
+        failures.len(),
+        count
+      ));
+    }
+    Ok(results)
+  }
+
+  pub fn for_each<T, F>(&self, items: Vec<T>, job: F) -> Result<(), RubxError>
+  where
+    T: Send,
+    F: Fn(T) + Sync,
+  {
+    dbg_call!();
+    self.map(items, job).map_err(|err| dbg_bleb!(err))?;
+    Ok(())
+  }
+
+  pub fn shutdown(mut self) -> Result<(), RubxError> {
+    dbg_call!();
+    self.shutdown_make().map_err(|err| dbg_bleb!(err))
+  }
+
+  fn shutdown_make(&mut self) -> Result<(), RubxError> {
+    drop(self.sender.take());
+    let mut first_err = None;
+    for worker in self.workers.drain(..) {
+      if let Err(err) = worker.join() {
+        if first_err.is_none() {
+          first_err = Some(dbg_erro!(panic_message(&err)));
+        }
+      }
+    }
+    match first_err {
+      Some(err) => Err(err),
+      None => Ok(()),
+    }
+  }
+}
+
+impl Drop for FirePool {
+  fn drop(&mut self) {
+    let _ = self.shutdown_make();
+  }
+}
+
+impl<T> FireTask<T> {
+  pub fn join(self) -> Result<T, RubxError> {
+    self
+      .receiver
+      .recv()
+      .map_err(|err| dbg_erro!(err))?
+      .map_err(|err| dbg_bleb!(err))
+  }
+}
+
+fn catch_panic<T>(job: impl FnOnce() -> T) -> Result<T, RubxError> {
+  std::panic::catch_unwind(AssertUnwindSafe(job))
+    .map_err(|err| dbg_erro!("The job has panicked", panic_message(&err)))
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> String {
+  if let Some(message) = payload.downcast_ref::<&str>() {
+    String::from(*message)
+  } else if let Some(message) = payload.downcast_ref::<String>() {
+    message.clone()
+  } else {
+    String::from("Unknown panic")
+  }
+}
@@ -0,0 +1,369 @@
+use serde_json::{json, Value};
+
+use crate::rux_debug::{dbg_bleb, dbg_erro};
+use crate::rux_debug::{dbg_call, dbg_reav, dbg_step};
+use crate::rux_texts;
+use crate::RubxError;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HostInfo {
+  pub os: String,
+  pub arch: String,
+  pub hostname: Option<String>,
+  pub kernel: Option<String>,
+  pub cpus_logical: usize,
+  pub cpus_physical: usize,
+  pub memory_total: Option<u64>,
+  pub memory_available: Option<u64>,
+  pub load_average: Option<(f64, f64, f64)>,
+  pub uptime: Option<f64>,
+  pub user_id: Option<u32>,
+  pub user_name: Option<String>,
+  pub user_home: Option<String>,
+}
+
+impl HostInfo {
+  pub fn to_json(&self) -> Value {
+    dbg_call!();
+    dbg_reav!(json!({
+      "os": self.os,
+      "arch": self.arch,
+      "hostname": self.hostname,
+      "kernel": self.kernel,
+      "cpus_logical": self.cpus_logical,
+      "cpus_physical": self.cpus_physical,
+      "memory_total": self.memory_total,
+      "memory_available": self.memory_available,
+      "load_average": self.load_average.map(|(one, five, fifteen)| vec![one, five, fifteen]),
+      "uptime": self.uptime,
+      "user_id": self.user_id,
+      "user_name": self.user_name,
+      "user_home": self.user_home,
+    }))
+  }
+
+  pub fn to_json_text(&self) -> Result<String, RubxError> {
+    dbg_call!();
+    dbg_reav!(Ok(
+      serde_json::to_string_pretty(&self.to_json()).map_err(|err| dbg_erro!(err))?
+    ))
+  }
+}
+
+pub fn get() -> HostInfo {
+  dbg_call!();
+  dbg_reav!(HostInfo {
+    os: String::from(get_os()),
+    arch: String::from(get_arch()),
+    hostname: get_hostname().ok(),
+    kernel: get_kernel().ok(),
+    cpus_logical: get_cpus_logical(),
+    cpus_physical: get_cpus_physical(),
+    memory_total: get_memory_total().ok(),
+    memory_available: get_memory_available().ok(),
+    load_average: get_load_average().ok(),
+    uptime: get_uptime().ok(),
+    user_id: get_user_id().ok(),
+    user_name: get_user_name().ok(),
+    user_home: get_user_home().ok(),
+  })
+}
+
+pub fn get_os() -> &'static str {
+  dbg_call!();
+  dbg_reav!(std::env::consts::OS)
+}
+
+pub fn get_arch() -> &'static str {
+  dbg_call!();
+  dbg_reav!(std::env::consts::ARCH)
+}
+
+pub fn get_hostname() -> Result<String, RubxError> {
+  dbg_call!();
+  if let Ok(hostname) = read_proc("/proc/sys/kernel/hostname") {
+    dbg_reav!(Ok(hostname));
+  }
+  #[cfg(unix)]
+  {
+    dbg_reav!(Ok(
+      uname_field(|name| &name.nodename).map_err(|err| dbg_bleb!(err))?
+    ))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(std::env::var("COMPUTERNAME").map_err(|err| dbg_erro!(err)))
+  }
+}
+
+pub fn get_kernel() -> Result<String, RubxError> {
+  dbg_call!();
+  if let Ok(kernel) = read_proc("/proc/sys/kernel/osrelease") {
+    dbg_reav!(Ok(kernel));
+  }
+  #[cfg(unix)]
+  {
+    dbg_reav!(Ok(
+      uname_field(|name| &name.release).map_err(|err| dbg_bleb!(err))?
+    ))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(Err(dbg_erro!("Could not find the kernel version")))
+  }
+}
+
+pub fn get_cpus_logical() -> usize {
+  dbg_call!();
+  dbg_reav!(num_cpus::get())
+}
+
+pub fn get_cpus_physical() -> usize {
+  dbg_call!();
+  dbg_reav!(num_cpus::get_physical())
+}
+
+pub fn get_memory_total() -> Result<u64, RubxError> {
+  dbg_call!();
+  dbg_reav!(read_meminfo("MemTotal").map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn get_memory_available() -> Result<u64, RubxError> {
+  dbg_call!();
+  dbg_reav!(read_meminfo("MemAvailable").map_err(|err| dbg_bleb!(err)))
+}
+
+fn read_meminfo(key: &str) -> Result<u64, RubxError> {
+  let meminfo = read_proc("/proc/meminfo").map_err(|err| dbg_bleb!(err))?;
+  for line in meminfo.lines() {
+    let mut parts = line.split_whitespace();
+    if parts.next() == Some(&format!("{}:", key)) {
+      let value = parts
+        .next()
+        .ok_or("Could not find the memory value")
+        .map_err(|err| dbg_erro!(err, key))?
+        .parse::<u64>()
+        .map_err(|err| dbg_erro!(err, key))?;
+      let unit = parts.next().unwrap_or_default();
+      dbg_step!(value, unit);
+      return Ok(if unit.eq_ignore_ascii_case("kb") {
+        value * 1024
+      } else {
+        value
+      });
+    }
+  }
+  Err(dbg_erro!("Could not find the memory key", key))
+}
+
+pub fn get_load_average() -> Result<(f64, f64, f64), RubxError> {
+  dbg_call!();
+  let loadavg = read_proc("/proc/loadavg").map_err(|err| dbg_bleb!(err))?;
+  let values = loadavg
+    .split_whitespace()
+    .take(3)
+    .map(|value| value.parse::<f64>().map_err(|err| dbg_erro!(err, value)))
+    .collect::<Result<Vec<f64>, RubxError>>()?;
+  if values.len() < 3 {
+    return Err(dbg_erro!("Could not parse the load average", loadavg));
+  }
+  dbg_reav!(Ok((values[0], values[1], values[2])))
+}
+
+pub fn get_uptime() -> Result<f64, RubxError> {
+  dbg_call!();
+  let uptime = read_proc("/proc/uptime").map_err(|err| dbg_bleb!(err))?;
+  dbg_reav!(uptime
+    .split_whitespace()
+    .next()
+    .ok_or("Could not find the uptime value")
+    .map_err(|err| dbg_erro!(err))?
+    .parse::<f64>()
+    .map_err(|err| dbg_erro!(err)))
+}
+
+pub fn get_user_id() -> Result<u32, RubxError> {
+  dbg_call!();
+  #[cfg(unix)]
+  {
+    dbg_reav!(Ok(unsafe { libc::getuid() }))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(Err(dbg_erro!("User ids are only supported on unix")))
+  }
+}
+
+pub fn get_user_euid() -> Result<u32, RubxError> {
+  dbg_call!();
+  #[cfg(unix)]
+  {
+    dbg_reav!(Ok(unsafe { libc::geteuid() }))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(Err(dbg_erro!("User ids are only supported on unix")))
+  }
+}
+
+pub fn get_group_id() -> Result<u32, RubxError> {
+  dbg_call!();
+  #[cfg(unix)]
+  {
+    dbg_reav!(Ok(unsafe { libc::getgid() }))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(Err(dbg_erro!("Group ids are only supported on unix")))
+  }
+}
+
+pub fn get_group_egid() -> Result<u32, RubxError> {
+  dbg_call!();
+  #[cfg(unix)]
+  {
+    dbg_reav!(Ok(unsafe { libc::getegid() }))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(Err(dbg_erro!("Group ids are only supported on unix")))
+  }
+}
+
+pub fn get_user_name() -> Result<String, RubxError> {
+  dbg_call!();
+  #[cfg(unix)]
+  {
+    if let Ok((name, _)) = passwd_of(unsafe { libc::getuid() }) {
+      dbg_reav!(Ok(name));
+    }
+  }
+  dbg_reav!(std::env::var("USER")
+    .or_else(|_| std::env::var("USERNAME"))
+    .map_err(|err| dbg_erro!(err)))
+}
+
+pub fn get_user_home() -> Result<String, RubxError> {
+  dbg_call!();
+  if let Ok(home) = std::env::var("HOME").or_else(|_| std::env::var("USERPROFILE")) {
+    if !home.is_empty() {
+      dbg_reav!(Ok(home));
+    }
+  }
+  #[cfg(unix)]
+  {
+    let (_, home) = passwd_of(unsafe { libc::getuid() }).map_err(|err| dbg_bleb!(err))?;
+    dbg_reav!(Ok(home))
+  }
+  #[cfg(not(unix))]
+  {
+    dbg_reav!(Err(dbg_erro!("Could not find the user home")))
+  }
+}
+
+fn read_proc(path: &str) -> Result<String, RubxError> {
+  if !crate::rux_fires::is_lin() {
+    return Err(dbg_erro!(
+      "The proc file system is only read on linux",
+      path
+    ));
+  }
+  Ok(
+    rux_texts::read(path)
+      .map_err(|err| dbg_bleb!(err))?
+      .trim()
+      .to_string(),
+  )
+}
+
+#[cfg(unix)]
+fn uname_field(field: fn(&libc::utsname) -> &[libc::c_char]) -> Result<String, RubxError> {
+  let mut name: libc::utsname = unsafe { std::mem::zeroed() };
+  if unsafe { libc::uname(&mut name) } != 0 {
+    return Err(dbg_erro!(std::io::Error::last_os_error()));
+  }
+  Ok(c_chars_to_string(field(&name)))
+}
+
+#[cfg(unix)]
+pub(crate) fn passwd_of(uid: u32) -> Result<(String, String), RubxError> {
+  let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+  let mut found: *mut libc::passwd = std::ptr::null_mut();
+  let mut buffer = vec![0 as libc::c_char; 16384];
+  let result = unsafe {
+    libc::getpwuid_r(
+      uid,
+      &mut passwd,
+      buffer.as_mut_ptr(),
+      buffer.len(),
+      &mut found,
+    )
+  };
+  if result != 0 || found.is_null() {
+    return Err(dbg_erro!("Could not find the user entry", uid));
+  }
+  let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+  let home = unsafe { std::ffi::CStr::from_ptr(passwd.pw_dir) };
+  Ok((
+    name.to_string_lossy().into_owned(),
+    home.to_string_lossy().into_owned(),
+  ))
+}
+
+#[cfg(unix)]
+pub(crate) fn passwd_named(name: &str) -> Result<(u32, u32), RubxError> {
+  let name_c = std::ffi::CString::new(name).map_err(|err| dbg_erro!(err, name))?;
+  let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+  let mut found: *mut libc::passwd = std::ptr::null_mut();
+  let mut buffer = vec![0 as libc::c_char; 16384];
+  let result = unsafe {
+    libc::getpwnam_r(
+      name_c.as_ptr(),
+      &mut passwd,
+      buffer.as_mut_ptr(),
+      buffer.len(),
+      &mut found,
+    )
+  };
+  if result != 0 || found.is_null() {
+    return Err(dbg_erro!("Could not find the user", name));
+  }
+  Ok((passwd.pw_uid, passwd.pw_gid))
+}
+
+#[cfg(unix)]
+pub(crate) fn groups_of(name: &str, gid: u32) -> Result<Vec<u32>, RubxError> {
+  let name_c = std::ffi::CString::new(name).map_err(|err| dbg_erro!(err, name))?;
+  let mut size: libc::c_int = 32;
+  loop {
+    let mut groups: Vec<libc::gid_t> = vec![0; size as usize];
+    #[cfg(target_vendor = "apple")]
+    let result = unsafe {
+      let list = groups.as_mut_ptr() as *mut libc::c_int;
+      libc::getgrouplist(name_c.as_ptr(), gid as libc::c_int, list, &mut size)
+    };
+    #[cfg(not(target_vendor = "apple"))]
+    let result =
+      unsafe { libc::getgrouplist(name_c.as_ptr(), gid, groups.as_mut_ptr(), &mut size) };
+    if result >= 0 {
+      groups.truncate(size as usize);
+      return Ok(groups);
+    }
+    if size as usize <= groups.len() {
+      size = (groups.len() * 2) as libc::c_int;
+    }
+    if size > 65536 {
+      return Err(dbg_erro!("Could not list the user groups", name));
+    }
+  }
+}
+
+#[cfg(unix)]
+fn c_chars_to_string(chars: &[libc::c_char]) -> String {
+  let bytes = chars
+    .iter()
+    .take_while(|ch| **ch != 0)
+    .map(|ch| *ch as u8)
+    .collect::<Vec<u8>>();
+  String::from_utf8_lossy(&bytes).into_owned()
+}
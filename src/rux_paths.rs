@@ -1,9 +1,336 @@
-use std::path::{Path, PathBuf};
+use std::collections::VecDeque;
+use std::ffi::{OsStr, OsString};
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Condvar, Mutex};
 
-use crate::rux_debug::{dbg_call, dbg_ifis, dbg_lets, dbg_muts, dbg_reav};
-use crate::rux_debug::{dbg_erro, dbg_step, dbg_tell};
+use crate::rux_debug::{dbg_bleb, dbg_erro, dbg_step, dbg_tell, dbg_warn};
+use crate::rux_debug::{dbg_call, dbg_ifis, dbg_muts, dbg_reav};
 use crate::{RubxError, RubxResult};
 
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RubxPath(PathBuf);
+
+impl RubxPath {
+  pub fn new(path: impl Into<PathBuf>) -> RubxPath {
+    RubxPath(path.into())
+  }
+
+  pub fn wd() -> Result<RubxPath, RubxError> {
+    dbg_call!();
+    dbg_reav!(Ok(RubxPath(
+      std::env::current_dir().map_err(|err| dbg_erro!(err))?
+    )))
+  }
+
+  pub fn as_path(&self) -> &Path {
+    &self.0
+  }
+
+  pub fn into_path_buf(self) -> PathBuf {
+    self.0
+  }
+
+  pub fn to_text(&self) -> Result<String, RubxError> {
+    dbg_call!(self);
+    dbg_reav!(text_of(&self.0).map_err(|err| dbg_bleb!(err)))
+  }
+
+  pub fn sep(&self) -> &'static str {
+    dbg_call!(self);
+    let bytes = self.0.as_os_str().as_encoded_bytes();
+    if cfg!(windows) && !bytes.contains(&b'\\') && bytes.contains(&b'/') {
+      dbg_reav!("/")
+    } else {
+      dbg_reav!(std::path::MAIN_SEPARATOR_STR)
+    }
+  }
+
+  pub fn has(&self) -> bool {
+    dbg_call!(self);
+    dbg_reav!(self.0.exists())
+  }
+
+  pub fn is_dir(&self) -> bool {
+    dbg_call!(self);
+    dbg_reav!(self.0.is_dir())
+  }
+
+  pub fn is_file(&self) -> bool {
+    dbg_call!(self);
+    dbg_reav!(self.0.is_file())
+  }
+
+  pub fn is_symlink(&self) -> bool {
+    dbg_call!(self);
+    dbg_reav!(self.0.is_symlink())
+  }
+
+  pub fn is_absolute(&self) -> bool {
+    dbg_call!(self);
+    dbg_reav!(self.0.is_absolute())
+  }
+
+  pub fn is_relative(&self) -> bool {
+    dbg_call!(self);
+    dbg_reav!(self.0.is_relative())
+  }
+
+  pub fn name(&self) -> Option<&OsStr> {
+    dbg_call!(self);
+    dbg_reav!(self.0.file_name())
+  }
+
+  pub fn stem(&self) -> Option<&OsStr> {
+    dbg_call!(self);
+    dbg_reav!(self.0.file_stem())
+  }
+
+  pub fn ext(&self) -> Option<&OsStr> {
+    dbg_call!(self);
+    dbg_reav!(self.0.extension())
+  }
+
+  pub fn ext_is(&self, ext: &str) -> bool {
+    dbg_call!(self, ext);
+    let ext = ext.trim_start_matches('.');
+    dbg_reav!(self
+      .ext()
+      .and_then(|own| own.to_str())
+      .map(|own| own.eq_ignore_ascii_case(ext))
+      .unwrap_or(false))
+  }
+
+  pub fn parts(&self) -> Vec<&OsStr> {
+    dbg_call!(self);
+    dbg_reav!(self.0.components().map(|part| part.as_os_str()).collect())
+  }
+
+  pub fn absolute(&self) -> Result<RubxPath, RubxError> {
+    dbg_call!(self);
+    if self.is_absolute() {
+      return Ok(self.clone());
+    }
+    let working_dir = RubxPath::wd().map_err(|err| dbg_bleb!(err))?;
+    let mut result = working_dir.0;
+    for part in self.0.components() {
+      match part {
+        Component::CurDir => continue,
+        Component::ParentDir => {
+          if !result.pop() {
+            return Err(dbg_erro!("The base path went empty", self));
+          }
+        }
+        part => result.push(part),
+      }
+    }
+    dbg_reav!(Ok(RubxPath(result)))
+  }
+
+  pub fn parent(&self) -> Result<RubxPath, RubxError> {
+    dbg_call!(self);
+    let absolute = self.absolute().map_err(|err| dbg_bleb!(err))?;
+    dbg_reav!(Ok(RubxPath(
+      absolute
+        .0
+        .parent()
+        .ok_or("The path parts went empty")
+        .map_err(|err| dbg_erro!(err, absolute))?
+        .to_path_buf()
+    )))
+  }
+
+  pub fn parent_find(&self, with_name: impl AsRef<OsStr>) -> Result<RubxPath, RubxError> {
+    dbg_call!(self);
+    let absolute = self.absolute().map_err(|err| dbg_bleb!(err))?;
+    let with_name = with_name.as_ref();
+    dbg_reav!(Ok(RubxPath(
+      absolute
+        .0
+        .ancestors()
+        .find(|ancestor| ancestor.file_name() == Some(with_name))
+        .ok_or("The path parts went empty")
+        .map_err(|err| dbg_erro!(err, absolute))?
+        .to_path_buf()
+    )))
+  }
+
+  pub fn join(&self, child: impl AsRef<Path>) -> Result<RubxPath, RubxError> {
+    let child = child.as_ref();
+    dbg_call!(self, child);
+    if child.as_os_str().is_empty() {
+      return Ok(self.clone());
+    }
+    if child.is_absolute() {
+      return Err(dbg_erro!("The child must be relative", child));
+    }
+    let mut result = self.0.clone();
+    for part in child.components() {
+      match part {
+        Component::CurDir => continue,
+        Component::ParentDir => {
+          let popped = matches!(result.components().next_back(), Some(Component::Normal(_)))
+            && result.pop();
+          if !popped {
+            if self.is_relative() {
+              let absolute = self.absolute().map_err(|err| dbg_bleb!(err))?;
+              return absolute.join(child);
+            }
+            return Err(dbg_erro!("The path parts went empty", self, child));
+          }
+        }
+        part => result.push(part),
+      }
+    }
+    dbg_reav!(Ok(RubxPath(result)))
+  }
+
+  pub fn relative(&self, base: impl AsRef<Path>) -> Result<RubxPath, RubxError> {
+    let base = base.as_ref();
+    dbg_call!(self, base);
+    let path = self.absolute().map_err(|err| dbg_bleb!(err))?.normalize();
+    let base = RubxPath::new(base)
+      .absolute()
+      .map_err(|err| dbg_bleb!(err))?
+      .normalize();
+    let path_parts = path.0.components().collect::<Vec<Component>>();
+    let base_parts = base.0.components().collect::<Vec<Component>>();
+    let common = path_parts
+      .iter()
+      .zip(base_parts.iter())
+      .take_while(|(path_part, base_part)| path_part == base_part)
+      .count();
+    dbg_step!(common);
+    if common == 0 {
+      return Err(dbg_erro!(
+        "The path and the base have no common root",
+        path,
+        base
+      ));
+    }
+    let mut result = PathBuf::new();
+    if common == base_parts.len() {
+      result.push(Component::CurDir);
+    }
+    for _ in common..base_parts.len() {
+      result.push(Component::ParentDir);
+    }
+    for part in &path_parts[common..] {
+      result.push(part);
+    }
+    dbg_reav!(Ok(RubxPath(result)))
+  }
+
+  pub fn normalize(&self) -> RubxPath {
+    dbg_call!(self);
+    let mut result = PathBuf::new();
+    for part in self.0.components() {
+      match part {
+        Component::CurDir => continue,
+        Component::ParentDir => match result.components().next_back() {
+          Some(Component::Normal(_)) => {
+            result.pop();
+          }
+          Some(Component::RootDir) | Some(Component::Prefix(_)) => continue,
+          _ => result.push(part),
+        },
+        part => result.push(part),
+      }
+    }
+    if result.as_os_str().is_empty() {
+      result.push(Component::CurDir);
+    }
+    dbg_reav!(RubxPath(result))
+  }
+
+  pub fn canonical(&self) -> Result<RubxPath, RubxError> {
+    dbg_call!(self);
+    dbg_reav!(Ok(RubxPath(
+      std::fs::canonicalize(&self.0).map_err(|err| dbg_erro!(err, self))?
+    )))
+  }
+
+  pub fn walk(&self) -> Result<RubxPath, RubxError> {
+    dbg_call!(self);
+    dbg_reav!(Ok(RubxPath(
+      std::fs::read_link(&self.0).map_err(|err| dbg_erro!(err, self))?
+    )))
+  }
+
+  pub fn list(&self) -> Result<Vec<RubxPath>, RubxError> {
+    dbg_call!(self);
+    let mut results = Vec::new();
+    for entry in std::fs::read_dir(&self.0).map_err(|err| dbg_erro!(err, self))? {
+      let entry = entry.map_err(|err| dbg_erro!(err, self))?;
+      results.push(RubxPath(entry.path()));
+    }
+    dbg_reav!(Ok(results))
+  }
+}
+
+impl AsRef<Path> for RubxPath {
+  fn as_ref(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl From<PathBuf> for RubxPath {
+  fn from(path: PathBuf) -> Self {
+    RubxPath(path)
+  }
+}
+
+impl From<&Path> for RubxPath {
+  fn from(path: &Path) -> Self {
+    RubxPath(path.to_path_buf())
+  }
+}
+
+impl From<&str> for RubxPath {
+  fn from(path: &str) -> Self {
+    RubxPath(PathBuf::from(path))
+  }
+}
+
+impl From<String> for RubxPath {
+  fn from(path: String) -> Self {
+    RubxPath(PathBuf::from(path))
+  }
+}
+
+impl From<RubxPath> for PathBuf {
+  fn from(path: RubxPath) -> Self {
+    path.0
+  }
+}
+
+impl<P: AsRef<Path>> FromIterator<P> for RubxPath {
+  fn from_iter<I: IntoIterator<Item = P>>(parts: I) -> Self {
+    RubxPath(parts.into_iter().collect())
+  }
+}
+
+fn text_of(path: &Path) -> Result<String, RubxError> {
+  Ok(
+    path
+      .to_str()
+      .ok_or("The path is not valid unicode")
+      .map_err(|err| dbg_erro!(err, path))?
+      .to_string(),
+  )
+}
+
+fn text_listed(path: &Path) -> Option<String> {
+  match path.to_str() {
+    Some(text) => Some(text.to_string()),
+    None => {
+      dbg_warn!("Skipping a path that is not valid unicode", path);
+      None
+    }
+  }
+}
+
 pub fn has(path: &str) -> bool {
   dbg_call!(path);
   dbg_reav!(Path::new(path).exists())
@@ -42,64 +369,378 @@ pub fn cd(path: &str) -> Result<(), RubxError> {
 
 pub fn wd() -> Result<String, RubxError> {
   dbg_call!();
-  dbg_reav!(Ok(format!(
-    "{}",
-    std::env::current_dir()
-      .map_err(|err| dbg_erro!(err))?
-      .display()
-  )))
+  dbg_reav!(RubxPath::wd().map_err(|err| dbg_bleb!(err))?.to_text())
 }
 
 pub fn rn(origin: &str, destiny: &str) -> Result<(), RubxError> {
   dbg_call!(origin, destiny);
-  std::fs::rename(origin, destiny).map_err(|err| dbg_erro!(err, origin, destiny))?;
-  Ok(())
+  move_path(Path::new(origin), Path::new(destiny)).map_err(|err| dbg_bleb!(err))
 }
 
 pub fn cp(origin: &str, destiny: &str) -> Result<(), RubxError> {
   dbg_call!(origin, destiny);
-  let destiny = dbg_lets!(if dbg_ifis!(is_file(origin) && is_dir(destiny)) {
-    path_join(destiny, path_name(origin)).map_err(|err| dbg_erro!(err, origin, destiny))?
+  cp_with(origin, destiny, &CopySetup::default()).map_err(|err| dbg_bleb!(err))?;
+  Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyExists {
+  Skip,
+  Overwrite,
+  OverwriteIfNewer,
+  Error,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CopyProgress {
+  pub files: u64,
+  pub bytes: u64,
+  pub path: PathBuf,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CopyReport {
+  pub copied: Vec<PathBuf>,
+  pub skipped: Vec<PathBuf>,
+  pub files: u64,
+  pub bytes: u64,
+}
+
+type CopyNotify = std::sync::Arc<dyn Fn(&CopyProgress) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct CopySetup {
+  exists: CopyExists,
+  preserve_mode: bool,
+  preserve_mtime: bool,
+  preserve_links: bool,
+  follow_links: bool,
+  hidden: bool,
+  dry_run: bool,
+  include: Option<PathFilter>,
+  exclude: Option<PathFilter>,
+  progress: Option<CopyNotify>,
+}
+
+impl Default for CopySetup {
+  fn default() -> Self {
+    CopySetup {
+      exists: CopyExists::Overwrite,
+      preserve_mode: true,
+      preserve_mtime: false,
+      preserve_links: false,
+      follow_links: false,
+      hidden: true,
+      dry_run: false,
+      include: None,
+      exclude: None,
+      progress: None,
+    }
+  }
+}
+
+impl std::fmt::Debug for CopySetup {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("CopySetup")
+      .field("exists", &self.exists)
+      .field("preserve_mode", &self.preserve_mode)
+      .field("preserve_mtime", &self.preserve_mtime)
+      .field("preserve_links", &self.preserve_links)
+      .field("follow_links", &self.follow_links)
+      .field("hidden", &self.hidden)
+      .field("dry_run", &self.dry_run)
+      .field("include", &self.include.is_some())
+      .field("exclude", &self.exclude.is_some())
+      .field("progress", &self.progress.is_some())
+      .finish()
+  }
+}
+
+impl CopySetup {
+  pub fn exists(mut self, exists: CopyExists) -> Self {
+    self.exists = exists;
+    self
+  }
+
+  pub fn preserve_mode(mut self, preserve: bool) -> Self {
+    self.preserve_mode = preserve;
+    self
+  }
+
+  pub fn preserve_mtime(mut self, preserve: bool) -> Self {
+    self.preserve_mtime = preserve;
+    self
+  }
+
+  pub fn preserve_links(mut self, preserve: bool) -> Self {
+    self.preserve_links = preserve;
+    self
+  }
+
+  pub fn follow_links(mut self, follow: bool) -> Self {
+    self.follow_links = follow;
+    self
+  }
+
+  pub fn hidden(mut self, hidden: bool) -> Self {
+    self.hidden = hidden;
+    self
+  }
+
+  pub fn dry_run(mut self, dry_run: bool) -> Self {
+    self.dry_run = dry_run;
+    self
+  }
+
+  pub fn include(
+    mut self,
+    include: impl Fn(&PathEntry) -> bool + Send + Sync + 'static,
+  ) -> Self {
+    self.include = Some(std::sync::Arc::new(include));
+    self
+  }
+
+  pub fn exclude(
+    mut self,
+    exclude: impl Fn(&PathEntry) -> bool + Send + Sync + 'static,
+  ) -> Self {
+    self.exclude = Some(std::sync::Arc::new(exclude));
+    self
+  }
+
+  pub fn progress(mut self, progress: impl Fn(&CopyProgress) + Send + Sync + 'static) -> Self {
+    self.progress = Some(std::sync::Arc::new(progress));
+    self
+  }
+}
+
+pub fn cp_with(
+  origin: &str,
+  destiny: &str,
+  setup: &CopySetup,
+) -> Result<CopyReport, RubxError> {
+  dbg_call!(origin, destiny, setup);
+  let origin_path = Path::new(origin);
+  let mut destiny_path = PathBuf::from(destiny);
+  let origin_meta = if setup.preserve_links {
+    std::fs::symlink_metadata(origin_path)
   } else {
-    destiny.to_string()
-  });
-  if dbg_ifis!(is_dir(origin)) {
-    copy_directory(origin, &destiny).map_err(|err| dbg_erro!(err, origin, destiny))?;
+    std::fs::metadata(origin_path)
+  }
+  .map_err(|err| dbg_erro!(err, origin))?;
+  if dbg_ifis!(!origin_meta.is_dir() && is_dir(destiny)) {
+    if let Some(name) = origin_path.file_name() {
+      destiny_path.push(name);
+    }
+  }
+  let mut report = CopyReport::default();
+  let mut walker = walk(origin)
+    .sort(true)
+    .hidden(setup.hidden)
+    .follow_links(setup.follow_links && !setup.preserve_links);
+  if let Some(exclude) = &setup.exclude {
+    let exclude = exclude.clone();
+    walker = walker.exclude(move |entry| exclude(entry));
+  }
+  let mut dirs = Vec::new();
+  for entry in walker {
+    let entry = entry.map_err(|err| dbg_bleb!(err))?;
+    let inside = entry
+      .path
+      .strip_prefix(origin_path)
+      .map_err(|err| dbg_erro!(err, entry.path))?;
+    let target = if inside.as_os_str().is_empty() {
+      destiny_path.clone()
+    } else {
+      destiny_path.join(inside)
+    };
+    dbg_tell!(entry, target);
+    let included = entry.depth == 0
+      || setup
+        .include
+        .as_ref()
+        .map(|include| include(&entry))
+        .unwrap_or(true);
+    if entry.is_dir && !(entry.is_symlink && setup.preserve_links) {
+      if included && !setup.dry_run {
+        std::fs::create_dir_all(&target).map_err(|err| dbg_erro!(err, target))?;
+      }
+      dirs.push((entry.path, target));
+      continue;
+    }
+    if !included {
+      continue;
+    }
+    copy_entry(&entry, &target, setup, &mut report).map_err(|err| dbg_bleb!(err))?;
+  }
+  if setup.preserve_mode || setup.preserve_mtime {
+    for (dir, target) in dirs.into_iter().rev() {
+      if !setup.dry_run && target.is_dir() {
+        copy_meta(&dir, &target, setup).map_err(|err| dbg_bleb!(err))?;
+      }
+    }
+  }
+  dbg_reav!(Ok(report))
+}
+
+fn copy_entry(
+  entry: &PathEntry,
+  target: &Path,
+  setup: &CopySetup,
+  report: &mut CopyReport,
+) -> Result<(), RubxError> {
+  let as_link = entry.is_symlink && (setup.preserve_links || entry.path.is_dir());
+  if let Ok(target_meta) = std::fs::symlink_metadata(target) {
+    let skip = match setup.exists {
+      CopyExists::Skip => true,
+      CopyExists::Overwrite => false,
+      CopyExists::OverwriteIfNewer => {
+        let origin_meta = if as_link {
+          std::fs::symlink_metadata(&entry.path)
+        } else {
+          std::fs::metadata(&entry.path)
+        };
+        let origin_time = origin_meta
+          .and_then(|meta| meta.modified())
+          .map_err(|err| dbg_erro!(err, entry.path))?;
+        let target_time = target_meta
+          .modified()
+          .map_err(|err| dbg_erro!(err, target))?;
+        origin_time <= target_time
+      }
+      CopyExists::Error => {
+        return Err(dbg_erro!("The destiny already exists", target));
+      }
+    };
+    if skip {
+      report.skipped.push(target.to_path_buf());
+      return Ok(());
+    }
+    if !setup.dry_run && (target_meta.file_type().is_symlink() || as_link) {
+      std::fs::remove_file(target).map_err(|err| dbg_erro!(err, target))?;
+    }
+  }
+  let mut progress = CopyProgress {
+    files: report.files,
+    bytes: report.bytes,
+    path: target.to_path_buf(),
+  };
+  if setup.dry_run {
+    if !as_link {
+      let size = std::fs::metadata(&entry.path)
+        .map_err(|err| dbg_erro!(err, entry.path))?
+        .len();
+      report.bytes += size;
+    }
   } else {
-    copy_file(origin, &destiny).map_err(|err| dbg_erro!(err, origin, destiny))?;
+    if let Some(parent) = target.parent() {
+      std::fs::create_dir_all(parent).map_err(|err| dbg_erro!(err, parent))?;
+    }
+    if as_link {
+      copy_link(&entry.path, target).map_err(|err| dbg_bleb!(err))?;
+    } else {
+      let mut origin_file =
+        std::fs::File::open(&entry.path).map_err(|err| dbg_erro!(err, entry.path))?;
+      let origin_meta = origin_file
+        .metadata()
+        .map_err(|err| dbg_erro!(err, entry.path))?;
+      let mut copy = |target_file: &mut std::fs::File| -> Result<(), RubxError> {
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+          let size = std::io::Read::read(&mut origin_file, &mut buffer)
+            .map_err(|err| dbg_erro!(err, entry.path))?;
+          if size == 0 {
+            break;
+          }
+          target_file
+            .write_all(&buffer[..size])
+            .map_err(|err| dbg_erro!(err, target))?;
+          report.bytes += size as u64;
+          progress.bytes = report.bytes;
+          if let Some(notify) = &setup.progress {
+            notify(&progress);
+          }
+        }
+        if setup.preserve_mtime {
+          copy_times(&origin_meta, target_file, target).map_err(|err| dbg_bleb!(err))?;
+        }
+        if setup.preserve_mode {
+          target_file
+            .set_permissions(origin_meta.permissions())
+            .map_err(|err| dbg_erro!(err, target))?;
+        }
+        Ok(())
+      };
+      if std::fs::symlink_metadata(target).is_ok() {
+        write_atomic_path(target, copy).map_err(|err| dbg_bleb!(err))?;
+      } else {
+        let mut target_file =
+          std::fs::File::create(target).map_err(|err| dbg_erro!(err, target))?;
+        copy(&mut target_file).map_err(|err| dbg_bleb!(err))?;
+      }
+    }
+  }
+  report.files += 1;
+  report.copied.push(target.to_path_buf());
+  progress.files = report.files;
+  progress.bytes = report.bytes;
+  if let Some(notify) = &setup.progress {
+    notify(&progress);
   }
   Ok(())
 }
 
-fn copy_directory(origin: &str, destiny: &str) -> Result<(), RubxError> {
-  dbg_call!(origin, destiny);
-  std::fs::create_dir_all(destiny).map_err(|err| dbg_erro!(err, destiny))?;
-  for entry in std::fs::read_dir(origin).map_err(|err| dbg_erro!(err, origin))? {
-    let entry = entry.map_err(|err| dbg_erro!(err))?;
-    let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
-    let entry_str = format!("{}", entry.path().display());
-    let entry_name = path_name(&entry_str);
-    let entry_dest =
-      path_join(&destiny, &entry_name).map_err(|err| dbg_erro!(err, destiny, entry_name))?;
-    if file_type.is_dir() {
-      copy_directory(&entry_str, &entry_dest)
-        .map_err(|err| dbg_erro!(err, entry_str, entry_dest))?;
+fn copy_link(origin: &Path, target: &Path) -> Result<(), RubxError> {
+  let link = std::fs::read_link(origin).map_err(|err| dbg_erro!(err, origin))?;
+  #[cfg(unix)]
+  {
+    std::os::unix::fs::symlink(&link, target).map_err(|err| dbg_erro!(err, link, target))?;
+  }
+  #[cfg(windows)]
+  {
+    if origin.is_dir() {
+      std::os::windows::fs::symlink_dir(&link, target)
+        .map_err(|err| dbg_erro!(err, link, target))?;
     } else {
-      std::fs::copy(&entry_str, &entry_dest)
-        .map_err(|err| dbg_erro!(err, entry_str, entry_dest))?;
+      std::os::windows::fs::symlink_file(&link, target)
+        .map_err(|err| dbg_erro!(err, link, target))?;
     }
   }
   Ok(())
 }
 
-fn copy_file(origin: &str, destiny: &str) -> Result<(), RubxError> {
-  dbg_call!(origin, destiny);
-  let parent = path_parent(destiny).map_err(|err| dbg_erro!(err, destiny))?;
-  std::fs::create_dir_all(&parent).map_err(|err| dbg_erro!(err, parent))?;
-  std::fs::copy(origin, destiny).map_err(|err| dbg_erro!(err, origin, destiny))?;
+fn copy_meta(origin: &Path, target: &Path, setup: &CopySetup) -> Result<(), RubxError> {
+  let meta = std::fs::metadata(origin).map_err(|err| dbg_erro!(err, origin))?;
+  if setup.preserve_mtime && (meta.is_file() || cfg!(unix)) {
+    let target_file = std::fs::OpenOptions::new()
+      .read(!meta.is_file())
+      .write(meta.is_file())
+      .open(target)
+      .map_err(|err| dbg_erro!(err, target))?;
+    copy_times(&meta, &target_file, target).map_err(|err| dbg_bleb!(err))?;
+  }
+  if setup.preserve_mode {
+    std::fs::set_permissions(target, meta.permissions())
+      .map_err(|err| dbg_erro!(err, target))?;
+  }
   Ok(())
 }
 
+fn copy_times(
+  meta: &std::fs::Metadata,
+  target_file: &std::fs::File,
+  target: &Path,
+) -> Result<(), RubxError> {
+  let modified = meta.modified().map_err(|err| dbg_erro!(err, target))?;
+  let mut times = std::fs::FileTimes::new().set_modified(modified);
+  if let Ok(accessed) = meta.accessed() {
+    times = times.set_accessed(accessed);
+  }
+  target_file
+    .set_times(times)
+    .map_err(|err| dbg_erro!(err, target))
+}
+
 pub fn cp_tmp(origin: &str, destiny: &str) -> Result<(), RubxError> {
   dbg_call!(origin, destiny);
   if has(destiny) {
@@ -110,18 +751,164 @@ pub fn cp_tmp(origin: &str, destiny: &str) -> Result<(), RubxError> {
       file_name.push_str("_");
       destiny_tmp = std::env::temp_dir().join(&file_name);
     }
-    let destiny_tmp = format!("{}", destiny_tmp.display());
+    let destiny_tmp = text_of(&destiny_tmp).map_err(|err| dbg_erro!(err, destiny))?;
     cp(destiny, &destiny_tmp).map_err(|err| dbg_erro!(err, destiny, destiny_tmp))?;
+    if is_file(origin) && is_file(destiny) {
+      return replace(origin, destiny).map_err(|err| dbg_bleb!(err));
+    }
     rm(destiny).map_err(|err| dbg_erro!(err, destiny))?;
   }
   cp(origin, destiny).map_err(|err| dbg_erro!(err, origin, destiny))?;
   Ok(())
 }
 
+pub fn replace(origin: &str, destiny: &str) -> Result<(), RubxError> {
+  dbg_call!(origin, destiny);
+  let mut source = std::fs::File::open(origin).map_err(|err| dbg_erro!(err, origin))?;
+  let meta = source.metadata().map_err(|err| dbg_erro!(err, origin))?;
+  if !meta.is_file() {
+    return Err(dbg_erro!("The origin must be a file", origin));
+  }
+  write_atomic_with(destiny, |file| {
+    std::io::copy(&mut source, file).map_err(|err| dbg_erro!(err, origin))?;
+    file
+      .set_permissions(meta.permissions())
+      .map_err(|err| dbg_erro!(err, destiny))?;
+    Ok(())
+  })
+  .map_err(|err| dbg_bleb!(err))
+}
+
 pub fn mv(origin: &str, destiny: &str) -> Result<(), RubxError> {
   dbg_call!(origin, destiny);
-  cp(origin, destiny).map_err(|err| dbg_erro!(err, origin, destiny))?;
-  rm(origin).map_err(|err| dbg_erro!(err, origin))?;
+  let origin_path = Path::new(origin);
+  let destiny_path = move_destiny(origin, destiny);
+  if dbg_ifis!(origin_path.is_dir() && !origin_path.is_symlink() && is_dir(destiny)) {
+    move_clash(origin_path, &destiny_path).map_err(|err| dbg_bleb!(err))?;
+    return move_merge(origin_path, &destiny_path).map_err(|err| dbg_bleb!(err));
+  }
+  move_path(origin_path, &destiny_path).map_err(|err| dbg_bleb!(err))
+}
+
+fn move_destiny(origin: &str, destiny: &str) -> PathBuf {
+  let mut destiny_path = PathBuf::from(destiny);
+  if dbg_ifis!(!is_dir(origin) && is_dir(destiny)) {
+    if let Some(name) = Path::new(origin).file_name() {
+      destiny_path.push(name);
+    }
+  }
+  destiny_path
+}
+
+fn move_path(origin: &Path, destiny: &Path) -> Result<(), RubxError> {
+  match std::fs::rename(origin, destiny) {
+    Ok(()) => Ok(()),
+    Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+      dbg_step!(err);
+      move_across(origin, destiny).map_err(|err| dbg_bleb!(err))
+    }
+    Err(err) => Err(dbg_erro!(err, origin, destiny)),
+  }
+}
+
+fn move_clash(origin: &Path, destiny: &Path) -> Result<(), RubxError> {
+  dbg_call!(origin, destiny);
+  for entry in std::fs::read_dir(origin).map_err(|err| dbg_erro!(err, origin))? {
+    let entry = entry.map_err(|err| dbg_erro!(err, origin))?;
+    let path = entry.path();
+    let target = destiny.join(entry.file_name());
+    let Ok(target_meta) = std::fs::symlink_metadata(&target) else {
+      continue;
+    };
+    let origin_dir = entry
+      .file_type()
+      .map_err(|err| dbg_erro!(err, path))?
+      .is_dir();
+    if origin_dir && target_meta.is_dir() {
+      move_clash(&path, &target).map_err(|err| dbg_bleb!(err))?;
+    } else if origin_dir || target_meta.is_dir() {
+      return Err(dbg_erro!(
+        "Could not merge a directory with a file of the same name",
+        path,
+        target
+      ));
+    }
+  }
+  Ok(())
+}
+
+fn move_merge(origin: &Path, destiny: &Path) -> Result<(), RubxError> {
+  dbg_call!(origin, destiny);
+  for entry in std::fs::read_dir(origin).map_err(|err| dbg_erro!(err, origin))? {
+    let entry = entry.map_err(|err| dbg_erro!(err, origin))?;
+    let path = entry.path();
+    let target = destiny.join(entry.file_name());
+    let file_type = entry.file_type().map_err(|err| dbg_erro!(err, path))?;
+    if file_type.is_dir() && target.is_dir() && !target.is_symlink() {
+      move_merge(&path, &target).map_err(|err| dbg_bleb!(err))?;
+    } else {
+      move_path(&path, &target).map_err(|err| dbg_bleb!(err))?;
+    }
+  }
+  std::fs::remove_dir(origin).map_err(|err| dbg_erro!(err, origin))
+}
+
+fn move_across(origin: &Path, destiny: &Path) -> Result<(), RubxError> {
+  dbg_call!(origin, destiny);
+  let name = destiny
+    .file_name()
+    .ok_or("The destiny must have a name")
+    .map_err(|err| dbg_erro!(err, destiny))?;
+  let parent = match destiny.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+    _ => PathBuf::from("."),
+  };
+  let mut temp_name = OsString::from(".");
+  temp_name.push(name);
+  temp_name.push(format!(
+    ".{}.{}.tmp",
+    std::process::id(),
+    crate::rux_rands::chars(8)
+  ));
+  let temp = parent.join(temp_name);
+  dbg_step!(temp);
+  let setup = CopySetup::default()
+    .exists(CopyExists::Error)
+    .preserve_mtime(true)
+    .preserve_links(true);
+  let origin_text = text_of(origin).map_err(|err| dbg_bleb!(err))?;
+  let temp_text = text_of(&temp).map_err(|err| dbg_bleb!(err))?;
+  let moved = cp_with(&origin_text, &temp_text, &setup)
+    .map_err(|err| dbg_bleb!(err))
+    .and_then(|_| std::fs::rename(&temp, destiny).map_err(|err| dbg_erro!(err, temp, destiny)));
+  if let Err(err) = moved {
+    if std::fs::symlink_metadata(&temp).is_ok() {
+      let undo = if temp.is_dir() && !temp.is_symlink() {
+        std::fs::remove_dir_all(&temp)
+      } else {
+        std::fs::remove_file(&temp)
+      };
+      if let Err(undo) = undo {
+        dbg_warn!("Could not roll back the partial copy", temp, undo);
+      }
+    }
+    return Err(dbg_bleb!(err));
+  }
+  let removed = if origin.is_dir() && !origin.is_symlink() {
+    std::fs::remove_dir_all(origin)
+  } else {
+    std::fs::remove_file(origin)
+  };
+  removed.map_err(|err| {
+    dbg_erro!(
+      format!(
+        "The origin could not be removed after the move, both copies remain: {}",
+        err
+      ),
+      origin,
+      destiny
+    )
+  })?;
   Ok(())
 }
 
@@ -154,33 +941,120 @@ pub fn touch(path: &str) -> Result<(), RubxError> {
   Ok(())
 }
 
-pub fn os_sep() -> &'static char {
-  dbg_call!();
-  &std::path::MAIN_SEPARATOR
+pub fn write_atomic(path: &str, contents: impl AsRef<[u8]>) -> Result<(), RubxError> {
+  dbg_call!(path);
+  write_atomic_with(path, |file| {
+    file
+      .write_all(contents.as_ref())
+      .map_err(|err| dbg_erro!(err))?;
+    Ok(())
+  })
+  .map_err(|err| dbg_bleb!(err))
 }
 
-pub fn path_sep(path: &str) -> &'static str {
+pub fn write_atomic_with(
+  path: &str,
+  act: impl FnOnce(&mut std::fs::File) -> Result<(), RubxError>,
+) -> Result<(), RubxError> {
   dbg_call!(path);
-  if path.contains("\\") {
-    "\\"
-  } else {
-    "/"
-  }
+  write_atomic_path(Path::new(path), act).map_err(|err| dbg_bleb!(err))
 }
 
-pub fn path_parts(path: &str) -> Vec<&str> {
-  dbg_call!(path);
-  let sep = path_sep(path);
-  let mut result: Vec<&str> = path.split(sep).collect();
-  if !result.is_empty() {
-    if result[0].is_empty() {
-      dbg_muts!(result[0], sep);
-    }
+fn write_atomic_path(
+  path: &Path,
+  act: impl FnOnce(&mut std::fs::File) -> Result<(), RubxError>,
+) -> Result<(), RubxError> {
+  let mut target = path.to_path_buf();
+  if target.is_symlink() {
+    target = std::fs::canonicalize(&target).map_err(|err| dbg_erro!(err, path))?;
   }
-  result
+  let name = target
+    .file_name()
+    .ok_or("The path must have a file name")
+    .map_err(|err| dbg_erro!(err, path))?
+    .to_os_string();
+  let parent = match target.parent() {
+    Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+    _ => PathBuf::from("."),
+  };
+  let mut temp_name = OsString::from(".");
+  temp_name.push(name);
+  temp_name.push(format!(
+    ".{}.{}.tmp",
+    std::process::id(),
+    crate::rux_rands::chars(8)
+  ));
+  let temp = parent.join(temp_name);
+  dbg_step!(target, temp);
+  let result = write_atomic_make(&target, &temp, &parent, act);
+  if result.is_err() {
+    let _ = std::fs::remove_file(&temp);
+  }
+  result.map_err(|err| dbg_bleb!(err))
 }
 
-pub fn path_parts_join(parts: &[&str]) -> String {
+fn write_atomic_make(
+  target: &Path,
+  temp: &Path,
+  parent: &Path,
+  act: impl FnOnce(&mut std::fs::File) -> Result<(), RubxError>,
+) -> Result<(), RubxError> {
+  let mut file = std::fs::OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .open(temp)
+    .map_err(|err| dbg_erro!(err, temp))?;
+  if let Ok(meta) = std::fs::metadata(target) {
+    file
+      .set_permissions(meta.permissions())
+      .map_err(|err| dbg_erro!(err, temp))?;
+  }
+  act(&mut file).map_err(|err| dbg_bleb!(err))?;
+  file.flush().map_err(|err| dbg_erro!(err, temp))?;
+  file.sync_all().map_err(|err| dbg_erro!(err, temp))?;
+  drop(file);
+  std::fs::rename(temp, target).map_err(|err| dbg_erro!(err, temp, target))?;
+  sync_dir(parent).map_err(|err| dbg_bleb!(err))
+}
+
+fn sync_dir(dir: &Path) -> Result<(), RubxError> {
+  #[cfg(unix)]
+  {
+    let dir_file = std::fs::File::open(dir).map_err(|err| dbg_erro!(err, dir))?;
+    dir_file.sync_all().map_err(|err| dbg_erro!(err, dir))?;
+  }
+  #[cfg(not(unix))]
+  {
+    let _ = dir;
+  }
+  Ok(())
+}
+
+pub fn os_sep() -> &'static char {
+  dbg_call!();
+  &std::path::MAIN_SEPARATOR
+}
+
+pub fn path_sep(path: &str) -> &'static str {
+  dbg_call!(path);
+  if path.contains("\\") {
+    "\\"
+  } else {
+    "/"
+  }
+}
+
+pub fn path_parts(path: &str) -> Vec<&str> {
+  dbg_call!(path);
+  let sep = path_sep(path);
+  let mut result: Vec<&str> = path.split(sep).collect();
+  if !result.is_empty() && result[0].is_empty() {
+    dbg_muts!(result[0], sep);
+  }
+  result
+}
+
+pub fn path_parts_join(parts: &[&str]) -> String {
   dbg_call!(parts);
   if parts.is_empty() {
     return String::default();
@@ -208,18 +1082,266 @@ pub fn path_parts_join(parts: &[&str]) -> String {
   } else {
     *os_sep()
   };
-  for index in start..end {
+  for part in &parts[start..end] {
     result.push(os_sep);
-    result.push_str(parts[index].as_ref());
+    result.push_str(part);
   }
   result
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PathStyle {
+  Posix,
+  Windows,
+}
+
+impl PathStyle {
+  pub fn native() -> PathStyle {
+    if crate::rux_fires::is_win() {
+      PathStyle::Windows
+    } else {
+      PathStyle::Posix
+    }
+  }
+
+  pub fn sep(&self) -> char {
+    match self {
+      PathStyle::Posix => '/',
+      PathStyle::Windows => '\\',
+    }
+  }
+
+  pub fn is_sep(&self, ch: char) -> bool {
+    match self {
+      PathStyle::Posix => ch == '/',
+      PathStyle::Windows => ch == '/' || ch == '\\',
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathPrefix {
+  Disk(char),
+  Unc(String, String),
+  Verbatim(String),
+  VerbatimDisk(char),
+  VerbatimUnc(String, String),
+  Device(String),
+}
+
+impl PathPrefix {
+  pub fn is_verbatim(&self) -> bool {
+    matches!(
+      self,
+      PathPrefix::Verbatim(_) | PathPrefix::VerbatimDisk(_) | PathPrefix::VerbatimUnc(_, _)
+    )
+  }
+
+  pub fn to_text(&self) -> String {
+    match self {
+      PathPrefix::Disk(disk) => format!("{}:", disk),
+      PathPrefix::Unc(server, share) => format!("\\\\{}\\{}", server, share),
+      PathPrefix::Verbatim(name) => format!("\\\\?\\{}", name),
+      PathPrefix::VerbatimDisk(disk) => format!("\\\\?\\{}:", disk),
+      PathPrefix::VerbatimUnc(server, share) => format!("\\\\?\\UNC\\{}\\{}", server, share),
+      PathPrefix::Device(name) => format!("\\\\.\\{}", name),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PathParsed {
+  pub style: PathStyle,
+  pub prefix: Option<PathPrefix>,
+  pub rooted: bool,
+  pub parts: Vec<String>,
+}
+
+impl PathParsed {
+  pub fn is_absolute(&self) -> bool {
+    match (self.style, &self.prefix) {
+      (PathStyle::Posix, _) => self.rooted,
+      (PathStyle::Windows, Some(PathPrefix::Disk(_))) => self.rooted,
+      (PathStyle::Windows, Some(_)) => true,
+      (PathStyle::Windows, None) => false,
+    }
+  }
+
+  pub fn is_relative(&self) -> bool {
+    !self.is_absolute()
+  }
+
+  pub fn name(&self) -> Option<&str> {
+    self
+      .parts
+      .last()
+      .map(|part| part.as_str())
+      .filter(|part| *part != "..")
+  }
+
+  pub fn parent(&self) -> Option<PathParsed> {
+    self.name()?;
+    let mut result = self.clone();
+    result.parts.pop();
+    Some(result)
+  }
+
+  pub fn join(&self, child: &str) -> PathParsed {
+    let child = path_parse(child, self.style);
+    if child.prefix.is_some() {
+      return child;
+    }
+    if child.rooted {
+      return PathParsed {
+        prefix: self.prefix.clone(),
+        ..child
+      };
+    }
+    let mut result = self.clone();
+    result.parts.extend(child.parts);
+    result
+  }
+
+  pub fn normalize(&self) -> PathParsed {
+    if matches!(&self.prefix, Some(prefix) if prefix.is_verbatim()) {
+      return self.clone();
+    }
+    let mut result = PathParsed {
+      parts: Vec::new(),
+      ..self.clone()
+    };
+    for part in &self.parts {
+      if part == ".." {
+        match result.parts.last() {
+          Some(last) if last != ".." => {
+            result.parts.pop();
+          }
+          _ if result.rooted => {}
+          _ => result.parts.push(part.clone()),
+        }
+      } else {
+        result.parts.push(part.clone());
+      }
+    }
+    result
+  }
+
+  pub fn to_style(&self, style: PathStyle) -> PathParsed {
+    PathParsed {
+      style,
+      ..self.clone()
+    }
+  }
+
+  pub fn to_text(&self) -> String {
+    let sep = self.style.sep();
+    let mut result = String::new();
+    if let Some(prefix) = &self.prefix {
+      result.push_str(&prefix.to_text());
+    }
+    if self.rooted {
+      result.push(sep);
+    }
+    result.push_str(&self.parts.join(&sep.to_string()));
+    if result.is_empty() {
+      result.push('.');
+    }
+    result
+  }
+}
+
+pub fn path_parse(path: &str, style: PathStyle) -> PathParsed {
+  dbg_call!(path, style);
+  let (prefix, rest) = match style {
+    PathStyle::Posix => (None, path),
+    PathStyle::Windows => path_parse_prefix(path),
+  };
+  dbg_step!(prefix, rest);
+  let verbatim = prefix
+    .as_ref()
+    .map(|prefix| prefix.is_verbatim())
+    .unwrap_or(false);
+  let is_sep = |ch: char| {
+    if verbatim {
+      ch == '\\'
+    } else {
+      style.is_sep(ch)
+    }
+  };
+  let rooted = rest.starts_with(is_sep)
+    || matches!(
+      prefix,
+      Some(PathPrefix::Unc(_, _))
+        | Some(PathPrefix::Verbatim(_))
+        | Some(PathPrefix::VerbatimUnc(_, _))
+        | Some(PathPrefix::Device(_))
+    );
+  let parts = rest
+    .split(is_sep)
+    .filter(|part| !part.is_empty() && (verbatim || *part != "."))
+    .map(String::from)
+    .collect::<Vec<String>>();
+  dbg_reav!(PathParsed {
+    style,
+    prefix,
+    rooted,
+    parts,
+  })
+}
+
+fn path_parse_prefix(path: &str) -> (Option<PathPrefix>, &str) {
+  let is_sep = |ch: char| ch == '\\' || ch == '/';
+  let split_name = |text: &str, seps: &dyn Fn(char) -> bool| -> (String, usize) {
+    let end = text.find(seps).unwrap_or(text.len());
+    (String::from(&text[..end]), end)
+  };
+  if let Some(rest) = path.strip_prefix("\\\\?\\") {
+    let only_back = |ch: char| ch == '\\';
+    if rest
+      .get(..4)
+      .is_some_and(|start| start.eq_ignore_ascii_case("UNC\\"))
+    {
+      let rest = &rest[4..];
+      let (server, end) = split_name(rest, &only_back);
+      let rest = rest[end..].trim_start_matches('\\');
+      let (share, end) = split_name(rest, &only_back);
+      return (Some(PathPrefix::VerbatimUnc(server, share)), &rest[end..]);
+    }
+    let chars = rest.chars().collect::<Vec<char>>();
+    if chars.len() >= 2 && chars[0].is_ascii_alphabetic() && chars[1] == ':' {
+      return (
+        Some(PathPrefix::VerbatimDisk(chars[0].to_ascii_uppercase())),
+        &rest[2..],
+      );
+    }
+    let (name, end) = split_name(rest, &only_back);
+    return (Some(PathPrefix::Verbatim(name)), &rest[end..]);
+  }
+  if let Some(rest) = path.strip_prefix("\\\\.\\") {
+    let (name, end) = split_name(rest, &is_sep);
+    return (Some(PathPrefix::Device(name)), &rest[end..]);
+  }
+  let chars = path.chars().take(3).collect::<Vec<char>>();
+  if chars.len() == 3 && is_sep(chars[0]) && is_sep(chars[1]) && !is_sep(chars[2]) {
+    let rest = &path[2..];
+    let (server, end) = split_name(rest, &is_sep);
+    let rest = rest[end..].trim_start_matches(is_sep);
+    let (share, end) = split_name(rest, &is_sep);
+    return (Some(PathPrefix::Unc(server, share)), &rest[end..]);
+  }
+  if chars.len() >= 2 && chars[0].is_ascii_alphabetic() && chars[1] == ':' {
+    return (
+      Some(PathPrefix::Disk(chars[0].to_ascii_uppercase())),
+      &path[2..],
+    );
+  }
+  (None, path)
+}
+
 pub fn path_name(path: &str) -> &str {
   dbg_call!(path);
   let parts = path_parts(path);
-  if parts.len() > 0 {
-    let last_part = parts[parts.len() - 1];
+  if let Some(last_part) = parts.last() {
     return last_part;
   }
   ""
@@ -228,8 +1350,7 @@ pub fn path_name(path: &str) -> &str {
 pub fn path_stem(path: &str) -> &str {
   dbg_call!(path);
   let parts = path_parts(path);
-  if parts.len() > 0 {
-    let last_part = parts[parts.len() - 1];
+  if let Some(last_part) = parts.last() {
     if let Some(last_dot) = last_part.rfind(".") {
       return &last_part[0..last_dot];
     }
@@ -240,8 +1361,7 @@ pub fn path_stem(path: &str) -> &str {
 pub fn path_ext(path: &str) -> &str {
   dbg_call!(path);
   let parts = path_parts(path);
-  if parts.len() > 0 {
-    let last_part = parts[parts.len() - 1];
+  if let Some(last_part) = parts.last() {
     if let Some(last_dot) = last_part.rfind(".") {
       return &last_part[last_dot..];
     }
@@ -267,135 +1387,63 @@ pub fn path_ext_is_on(path: &str, exts: &[impl AsRef<str> + std::fmt::Debug]) ->
 
 pub fn path_absolute(path: &str) -> Result<String, RubxError> {
   dbg_call!(path);
-  if is_absolute(path) {
-    return Ok(String::from(path));
-  }
-  let working_dir = wd().map_err(|err| dbg_erro!(err))?;
-  let mut base_parts = path_parts(&working_dir);
-  let path_parts = path_parts(&path);
-  for path_part in path_parts {
-    if path_part == "." {
-      continue;
-    } else if path_part == ".." {
-      if base_parts.pop().is_none() {
-        return Err(dbg_erro!("The base path went empty", path));
-      }
-    } else {
-      base_parts.push(path_part);
-    }
-  }
-  Ok(path_parts_join(base_parts.as_slice()))
+  RubxPath::new(path)
+    .absolute()
+    .map_err(|err| dbg_bleb!(err))?
+    .to_text()
 }
 
 pub fn path_relative(path: &str, base: &str) -> Result<String, RubxError> {
   dbg_call!(path, base);
-  if !is_absolute(path) {
-    return Ok(String::from(path));
-  }
-  let base = if !is_absolute(base) {
-    path_absolute(base).map_err(|err| dbg_erro!(err))?
-  } else {
-    String::from(base)
-  };
-  if !path.starts_with(&base) {
-    return Err(dbg_erro!("The path must starts with the base", path, base));
-  }
-  let sep = path_sep(path);
-  let result = &path[base.len()..];
-  if result.starts_with(sep) {
-    Ok(format!(".{}", result))
-  } else {
-    Ok(String::from(result))
-  }
+  RubxPath::new(path)
+    .relative(base)
+    .map_err(|err| dbg_bleb!(err))?
+    .to_text()
+}
+
+pub fn path_normalize(path: &str) -> Result<String, RubxError> {
+  dbg_call!(path);
+  RubxPath::new(path).normalize().to_text()
+}
+
+pub fn path_canonical(path: &str) -> Result<String, RubxError> {
+  dbg_call!(path);
+  RubxPath::new(path)
+    .canonical()
+    .map_err(|err| dbg_bleb!(err))?
+    .to_text()
 }
 
 pub fn path_walk(path: &str) -> Result<String, RubxError> {
   dbg_call!(path);
-  Ok(format!(
-    "{}",
-    std::fs::read_link(path)
-      .map_err(|err| dbg_erro!(err, path))?
-      .display(),
-  ))
+  RubxPath::new(path)
+    .walk()
+    .map_err(|err| dbg_bleb!(err))?
+    .to_text()
 }
 
 pub fn path_parent(path: &str) -> Result<String, RubxError> {
   dbg_call!(path);
-  let path = path_absolute(path).map_err(|err| dbg_erro!(err))?;
-  let mut parts = path_parts(&path);
-  if parts.pop().is_none() {
-    return Err(dbg_erro!("The path parts went empty", path));
-  }
-  Ok(path_parts_join(parts.as_slice()))
+  RubxPath::new(path)
+    .parent()
+    .map_err(|err| dbg_bleb!(err))?
+    .to_text()
 }
 
 pub fn path_parent_find(path: &str, with_name: &str) -> Result<String, RubxError> {
   dbg_call!(path, with_name);
-  let path = path_absolute(path).map_err(|err| dbg_erro!(err))?;
-  let mut parts = path_parts(&path);
-  loop {
-    if let Some(part) = parts.pop() {
-      if part == with_name {
-        parts.push(part);
-        return Ok(path_parts_join(parts.as_slice()));
-      }
-    } else {
-      return Err(dbg_erro!("The path parts went empty", path));
-    }
-  }
+  RubxPath::new(path)
+    .parent_find(with_name)
+    .map_err(|err| dbg_bleb!(err))?
+    .to_text()
 }
 
 pub fn path_join(path: &str, child: &str) -> Result<String, RubxError> {
   dbg_call!(path, child);
-  if child.is_empty() {
-    return Ok(String::from(path));
-  }
-  if is_absolute(child) {
-    return Err(dbg_erro!("The child must be relative", child));
-  }
-  let mut base_parts = path_parts(path)
-    .into_iter()
-    .map(String::from)
-    .collect::<Vec<String>>();
-  let initial_size = base_parts.len();
-  let has_more = !is_absolute(path);
-  let mut take_more = false;
-  let child_parts = path_parts(child);
-  let mut child_index = 0;
-  loop {
-    if child_index >= child_parts.len() {
-      break;
-    }
-    let part = child_parts[child_index];
-    child_index = child_index + 1;
-    if part == "." {
-      continue;
-    } else if part == ".." {
-      if base_parts.pop().is_none() {
-        if !take_more && has_more {
-          let abs_path = path_absolute(path).map_err(|err| dbg_erro!(err))?;
-          let abs_parts = path_parts(&abs_path);
-          let dif_parts = abs_parts.len() - initial_size;
-          for new_index in 0..dif_parts {
-            base_parts.insert(new_index, abs_parts[new_index].into());
-          }
-          take_more = true;
-          child_index = child_index - 1;
-        } else {
-          return Err(dbg_erro!("The path parts went empty", path, child));
-        }
-      }
-    } else {
-      base_parts.push(part.into());
-    }
-  }
-  Ok(path_parts_join(
-    base_parts
-      .iter()
-      .map(|p| p.as_ref())
-      .collect::<Vec<&str>>()
-      .as_slice(),
-  ))
+  RubxPath::new(path)
+    .join(child)
+    .map_err(|err| dbg_bleb!(err))?
+    .to_text()
 }
 
 pub fn path_join_if_relative(base: &str, path: &str) -> Result<String, RubxError> {
@@ -415,29 +1463,18 @@ pub fn path_list(path: &str) -> Result<Vec<String>, RubxError> {
   let entries = std::fs::read_dir(path).map_err(|err| dbg_erro!(err))?;
   for entry in entries {
     let entry = entry.map_err(|err| dbg_erro!(err))?;
-    results.push(format!("{}", entry.path().display()));
+    results.extend(text_listed(&entry.path()));
   }
   Ok(results)
 }
 
 pub fn path_list_in(path: &str) -> Result<Vec<String>, RubxError> {
   dbg_call!(path);
-  let mut results = Vec::new();
-  path_list_in_make(path, &mut results).map_err(|err| dbg_erro!(err))?;
-  Ok(results)
+  path_list_walk(path).list().map_err(|err| dbg_bleb!(err))
 }
 
-fn path_list_in_make(path: &str, results: &mut Vec<String>) -> Result<(), RubxError> {
-  for entry in std::fs::read_dir(path).map_err(|err| dbg_erro!(err))? {
-    let entry = entry.map_err(|err| dbg_erro!(err))?;
-    let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
-    let inside = format!("{}", entry.path().display());
-    if file_type.is_dir() {
-      path_list_in_make(&inside, results).map_err(|err| dbg_erro!(err))?;
-    }
-    results.push(inside);
-  }
-  Ok(())
+fn path_list_walk(path: &str) -> PathWalker {
+  walk(path).min_depth(1).post_order(true)
 }
 
 pub fn path_list_dirs(path: &str) -> Result<Vec<String>, RubxError> {
@@ -448,7 +1485,7 @@ pub fn path_list_dirs(path: &str) -> Result<Vec<String>, RubxError> {
     let entry = entry.map_err(|err| dbg_erro!(err))?;
     let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
     if file_type.is_dir() {
-      results.push(format!("{}", entry.path().display()));
+      results.extend(text_listed(&entry.path()));
     }
   }
   Ok(results)
@@ -456,22 +1493,10 @@ pub fn path_list_dirs(path: &str) -> Result<Vec<String>, RubxError> {
 
 pub fn path_list_dirs_in(path: &str) -> Result<Vec<String>, RubxError> {
   dbg_call!(path);
-  let mut results = Vec::new();
-  path_list_dirs_in_make(path, &mut results).map_err(|err| dbg_erro!(err))?;
-  return Ok(results);
-}
-
-fn path_list_dirs_in_make(path: &str, results: &mut Vec<String>) -> Result<(), RubxError> {
-  for entry in std::fs::read_dir(path).map_err(|err| dbg_erro!(err))? {
-    let entry = entry.map_err(|err| dbg_erro!(err))?;
-    let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
-    if file_type.is_dir() {
-      let inside = format!("{}", entry.path().display());
-      path_list_dirs_in_make(&inside, results).map_err(|err| dbg_erro!(err))?;
-      results.push(inside);
-    }
-  }
-  Ok(())
+  path_list_walk(path)
+    .include(|entry| entry.is_dir)
+    .list()
+    .map_err(|err| dbg_bleb!(err))
 }
 
 pub fn path_list_files(path: &str) -> Result<Vec<String>, RubxError> {
@@ -482,7 +1507,7 @@ pub fn path_list_files(path: &str) -> Result<Vec<String>, RubxError> {
     let entry = entry.map_err(|err| dbg_erro!(err))?;
     let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
     if file_type.is_file() {
-      results.push(format!("{}", entry.path().display()));
+      results.extend(text_listed(&entry.path()));
     }
   }
   Ok(results)
@@ -490,24 +1515,10 @@ pub fn path_list_files(path: &str) -> Result<Vec<String>, RubxError> {
 
 pub fn path_list_files_in(path: &str) -> Result<Vec<String>, RubxError> {
   dbg_call!(path);
-  let mut results = Vec::new();
-  path_list_files_in_make(path, &mut results).map_err(|err| dbg_erro!(err))?;
-  Ok(results)
-}
-
-fn path_list_files_in_make(path: &str, results: &mut Vec<String>) -> Result<(), RubxError> {
-  for entry in std::fs::read_dir(path).map_err(|err| dbg_erro!(err))? {
-    let entry = entry.map_err(|err| dbg_erro!(err))?;
-    let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
-    let inside = format!("{}", entry.path().display());
-    if file_type.is_dir() {
-      path_list_files_in_make(&inside, results).map_err(|err| dbg_erro!(err))?;
-    }
-    if file_type.is_file() {
-      results.push(inside);
-    }
-  }
-  Ok(())
+  path_list_walk(path)
+    .include(|entry| entry.is_file)
+    .list()
+    .map_err(|err| dbg_bleb!(err))
 }
 
 pub fn path_list_files_ext(path: &str, ext: &str) -> Result<Vec<String>, RubxError> {
@@ -518,7 +1529,9 @@ pub fn path_list_files_ext(path: &str, ext: &str) -> Result<Vec<String>, RubxErr
     let entry = entry.map_err(|err| dbg_erro!(err))?;
     let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
     if file_type.is_file() {
-      let name = format!("{}", entry.path().display());
+      let Some(name) = text_listed(&entry.path()) else {
+        continue;
+      };
       if name.to_lowercase().ends_with(&ext.to_lowercase()) {
         results.push(name);
       }
@@ -529,30 +1542,7 @@ pub fn path_list_files_ext(path: &str, ext: &str) -> Result<Vec<String>, RubxErr
 
 pub fn path_list_files_ext_in(path: &str, ext: &str) -> Result<Vec<String>, RubxError> {
   dbg_call!(path, ext);
-  let mut results = Vec::new();
-  path_list_files_ext_in_make(path, ext, &mut results).map_err(|err| dbg_erro!(err))?;
-  Ok(results)
-}
-
-fn path_list_files_ext_in_make(
-  path: &str,
-  ext: &str,
-  results: &mut Vec<String>,
-) -> Result<(), RubxError> {
-  for entry in std::fs::read_dir(path).map_err(|err| dbg_erro!(err))? {
-    let entry = entry.map_err(|err| dbg_erro!(err))?;
-    let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
-    let inside = format!("{}", entry.path().display());
-    if file_type.is_dir() {
-      path_list_files_ext_in_make(&inside, ext, results).map_err(|err| dbg_erro!(err))?;
-    }
-    if file_type.is_file() {
-      if inside.to_lowercase().ends_with(&ext.to_lowercase()) {
-        results.push(inside);
-      }
-    }
-  }
-  Ok(())
+  path_list_files_exts_in(path, &[ext]).map_err(|err| dbg_bleb!(err))
 }
 
 pub fn path_list_files_exts(path: &str, exts: &[&str]) -> Result<Vec<String>, RubxError> {
@@ -563,7 +1553,9 @@ pub fn path_list_files_exts(path: &str, exts: &[&str]) -> Result<Vec<String>, Ru
     let entry = entry.map_err(|err| dbg_erro!(err))?;
     let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
     if file_type.is_file() {
-      let name = format!("{}", entry.path().display());
+      let Some(name) = text_listed(&entry.path()) else {
+        continue;
+      };
       for ext in exts {
         if name.to_lowercase().ends_with(&ext.to_lowercase()) {
           results.push(name);
@@ -577,34 +1569,973 @@ pub fn path_list_files_exts(path: &str, exts: &[&str]) -> Result<Vec<String>, Ru
 
 pub fn path_list_files_exts_in(path: &str, exts: &[&str]) -> Result<Vec<String>, RubxError> {
   dbg_call!(path, exts);
-  let mut results = Vec::new();
-  path_list_files_exts_in_make(path, exts, &mut results).map_err(|err| dbg_erro!(err))?;
-  Ok(results)
+  let exts = exts
+    .iter()
+    .map(|ext| ext.to_lowercase())
+    .collect::<Vec<String>>();
+  path_list_walk(path)
+    .include(move |entry| {
+      let name = entry.path.to_str().map(|name| name.to_lowercase());
+      entry.is_file
+        && name
+          .map(|name| exts.iter().any(|ext| name.ends_with(ext)))
+          .unwrap_or(false)
+    })
+    .list()
+    .map_err(|err| dbg_bleb!(err))
 }
 
-fn path_list_files_exts_in_make(
-  path: &str,
-  exts: &[&str],
-  results: &mut Vec<String>,
+type PathFilter = std::sync::Arc<dyn Fn(&PathEntry) -> bool + Send + Sync>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathEntry {
+  pub path: PathBuf,
+  pub depth: usize,
+  pub is_dir: bool,
+  pub is_file: bool,
+  pub is_symlink: bool,
+}
+
+impl PathEntry {
+  pub fn name(&self) -> String {
+    self
+      .path
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_default()
+  }
+
+  pub fn is_hidden(&self) -> bool {
+    self.name().starts_with('.')
+  }
+}
+
+#[derive(Clone)]
+pub struct PathWalker {
+  root: PathBuf,
+  min_depth: usize,
+  max_depth: Option<usize>,
+  follow_links: bool,
+  hidden: bool,
+  sort: bool,
+  post_order: bool,
+  include: Option<PathFilter>,
+  exclude: Option<PathFilter>,
+  ignore_files: Vec<String>,
+}
+
+impl std::fmt::Debug for PathWalker {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("PathWalker")
+      .field("root", &self.root)
+      .field("min_depth", &self.min_depth)
+      .field("max_depth", &self.max_depth)
+      .field("follow_links", &self.follow_links)
+      .field("hidden", &self.hidden)
+      .field("sort", &self.sort)
+      .field("post_order", &self.post_order)
+      .field("include", &self.include.is_some())
+      .field("exclude", &self.exclude.is_some())
+      .field("ignore_files", &self.ignore_files)
+      .finish()
+  }
+}
+
+impl PathWalker {
+  pub fn min_depth(mut self, depth: usize) -> Self {
+    self.min_depth = depth;
+    self
+  }
+
+  pub fn max_depth(mut self, depth: usize) -> Self {
+    self.max_depth = Some(depth);
+    self
+  }
+
+  pub fn follow_links(mut self, follow: bool) -> Self {
+    self.follow_links = follow;
+    self
+  }
+
+  pub fn hidden(mut self, hidden: bool) -> Self {
+    self.hidden = hidden;
+    self
+  }
+
+  pub fn sort(mut self, sort: bool) -> Self {
+    self.sort = sort;
+    self
+  }
+
+  pub fn post_order(mut self, post_order: bool) -> Self {
+    self.post_order = post_order;
+    self
+  }
+
+  pub fn include(
+    mut self,
+    include: impl Fn(&PathEntry) -> bool + Send + Sync + 'static,
+  ) -> Self {
+    self.include = Some(std::sync::Arc::new(include));
+    self
+  }
+
+  pub fn exclude(
+    mut self,
+    exclude: impl Fn(&PathEntry) -> bool + Send + Sync + 'static,
+  ) -> Self {
+    self.exclude = Some(std::sync::Arc::new(exclude));
+    self
+  }
+
+  pub fn git_ignore(mut self, honor: bool) -> Self {
+    for name in [".gitignore", ".ignore"] {
+      self.ignore_files.retain(|file| file != name);
+      if honor {
+        self.ignore_files.push(String::from(name));
+      }
+    }
+    self
+  }
+
+  pub fn ignore_file(mut self, name: &str) -> Self {
+    if !self.ignore_files.iter().any(|file| file == name) {
+      self.ignore_files.push(String::from(name));
+    }
+    self
+  }
+
+  pub fn list(self) -> Result<Vec<String>, RubxError> {
+    dbg_call!(self);
+    let mut results = Vec::new();
+    for entry in self {
+      let entry = entry.map_err(|err| dbg_bleb!(err))?;
+      results.extend(text_listed(&entry.path));
+    }
+    dbg_reav!(Ok(results))
+  }
+
+  pub fn run(
+    self,
+    jobs: Option<usize>,
+    act: impl Fn(Result<PathEntry, RubxError>) + Send + Sync,
+  ) -> Result<(), RubxError> {
+    dbg_call!(self, jobs);
+    if self.post_order {
+      return Err(dbg_erro!("The parallel walk does not support post order"));
+    }
+    let jobs = jobs.unwrap_or_else(num_cpus::get).max(1);
+    let root =
+      walk_entry(self.root.clone(), 0, self.follow_links).map_err(|err| dbg_bleb!(err))?;
+    if self.accepts(&root) {
+      act(Ok(root.clone()));
+    }
+    if !self.descends(&root) {
+      return Ok(());
+    }
+    let state = PathRunState {
+      queue: Mutex::new(PathRunQueue {
+        jobs: VecDeque::from([PathWalkJob {
+          entry: root,
+          scopes: Vec::new(),
+          canonicals: Vec::new(),
+        }]),
+        pending: 1,
+        halted: false,
+      }),
+      ready: Condvar::new(),
+    };
+    let setup = &self;
+    let state = &state;
+    let act = &act;
+    std::thread::scope(|scope| -> Result<(), RubxError> {
+      let mut workers = Vec::new();
+      for index in 0..jobs {
+        let worker = std::thread::Builder::new()
+          .name(format!("rubx-walker-{}", index))
+          .spawn_scoped(scope, move || walk_run(setup, state, act))
+          .map_err(|err| dbg_erro!(err))?;
+        workers.push(worker);
+      }
+      let mut first_err = None;
+      for worker in workers {
+        let result = match worker.join() {
+          Ok(result) => result.map_err(|err| dbg_bleb!(err)),
+          Err(_) => Err(dbg_erro!("The walker thread has panicked")),
+        };
+        if let Err(err) = result {
+          first_err.get_or_insert(err);
+        }
+      }
+      match first_err {
+        Some(err) => Err(err),
+        None => Ok(()),
+      }
+    })
+  }
+
+  pub fn channel(self, jobs: Option<usize>) -> Receiver<Result<PathEntry, RubxError>> {
+    dbg_call!(self, jobs);
+    let (sender, receiver) = mpsc::channel();
+    let _ = std::thread::Builder::new()
+      .name(String::from("rubx-walker"))
+      .spawn(move || {
+        let sent = sender.clone();
+        if let Err(err) = self.run(jobs, move |entry| {
+          let _ = sent.send(entry);
+        }) {
+          let _ = sender.send(Err(err));
+        }
+      });
+    receiver
+  }
+
+  fn accepts(&self, entry: &PathEntry) -> bool {
+    entry.depth >= self.min_depth
+      && self
+        .include
+        .as_ref()
+        .map(|include| include(entry))
+        .unwrap_or(true)
+  }
+
+  fn descends(&self, entry: &PathEntry) -> bool {
+    entry.is_dir
+      && self
+        .max_depth
+        .map(|max_depth| entry.depth < max_depth)
+        .unwrap_or(true)
+  }
+
+  fn canonical(&self, entry: &PathEntry) -> Result<Option<PathBuf>, RubxError> {
+    if !self.follow_links {
+      return Ok(None);
+    }
+    Ok(Some(
+      std::fs::canonicalize(&entry.path).map_err(|err| dbg_erro!(err, entry.path))?,
+    ))
+  }
+
+  fn ignore_load(&self, dir: &Path) -> Result<PathIgnore, RubxError> {
+    let mut ignore = PathIgnore::default();
+    for name in &self.ignore_files {
+      let ignore_path = dir.join(name);
+      if ignore_path.is_file() {
+        let loaded = PathIgnore::load(&ignore_path).map_err(|err| dbg_bleb!(err))?;
+        ignore.rules.extend(loaded.rules);
+      }
+    }
+    Ok(ignore)
+  }
+
+  fn ignores<'a>(
+    &self,
+    entry: &PathEntry,
+    scopes: impl Iterator<Item = (&'a Path, &'a PathIgnore)>,
+  ) -> bool {
+    if entry.depth == 0 || self.ignore_files.is_empty() {
+      return false;
+    }
+    if entry.is_dir && entry.name() == ".git" {
+      return true;
+    }
+    let mut ignored = false;
+    for (base, ignore) in scopes {
+      if ignore.is_empty() {
+        continue;
+      }
+      if let Ok(relative) = entry.path.strip_prefix(base) {
+        let relative = relative
+          .components()
+          .map(|part| part.as_os_str().to_string_lossy().into_owned())
+          .collect::<Vec<String>>()
+          .join("/");
+        if let Some(matched) = ignore.matched(&relative, entry.is_dir) {
+          ignored = matched;
+        }
+      }
+    }
+    ignored
+  }
+
+  fn skips(&self, entry: &PathEntry) -> bool {
+    entry.depth > 0
+      && ((!self.hidden && entry.is_hidden())
+        || self
+          .exclude
+          .as_ref()
+          .map(|exclude| exclude(entry))
+          .unwrap_or(false))
+  }
+}
+
+impl IntoIterator for PathWalker {
+  type Item = Result<PathEntry, RubxError>;
+  type IntoIter = PathWalk;
+
+  fn into_iter(self) -> Self::IntoIter {
+    PathWalk {
+      start: Some(self.root.clone()),
+      setup: self,
+      frames: Vec::new(),
+    }
+  }
+}
+
+pub struct PathWalk {
+  setup: PathWalker,
+  start: Option<PathBuf>,
+  frames: Vec<PathWalkFrame>,
+}
+
+struct PathWalkFrame {
+  entries: Box<dyn Iterator<Item = std::io::Result<PathBuf>> + Send>,
+  entry: Option<PathEntry>,
+  canonical: Option<PathBuf>,
+  ignore: PathIgnore,
+}
+
+impl Iterator for PathWalk {
+  type Item = Result<PathEntry, RubxError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      let (path, depth) = if let Some(root) = self.start.take() {
+        (root, 0)
+      } else {
+        let frame = self.frames.last_mut()?;
+        match frame.entries.next() {
+          Some(Ok(path)) => (path, self.frames.len()),
+          Some(Err(err)) => return Some(Err(dbg_erro!(err))),
+          None => {
+            let frame = self.frames.pop()?;
+            if let Some(entry) = frame.entry.filter(|_| self.setup.post_order) {
+              if self.setup.accepts(&entry) {
+                return Some(Ok(entry));
+              }
+            }
+            continue;
+          }
+        }
+      };
+      let entry = match walk_entry(path, depth, self.setup.follow_links) {
+        Ok(entry) => entry,
+        Err(err) => return Some(Err(dbg_bleb!(err))),
+      };
+      if self.setup.skips(&entry)
+        || self.setup.ignores(
+          &entry,
+          self.frames.iter().filter_map(|frame| {
+            frame
+              .entry
+              .as_ref()
+              .map(|base| (base.path.as_path(), &frame.ignore))
+          }),
+        )
+      {
+        continue;
+      }
+      if let Some(result) = self.visit(entry) {
+        return Some(result);
+      }
+    }
+  }
+}
+
+impl PathWalk {
+  fn visit(&mut self, entry: PathEntry) -> Option<Result<PathEntry, RubxError>> {
+    if self.setup.descends(&entry) {
+      let canonical = match self.setup.canonical(&entry) {
+        Ok(canonical) => canonical,
+        Err(err) => return Some(Err(dbg_bleb!(err))),
+      };
+      if canonical.is_some() && self.frames.iter().any(|frame| frame.canonical == canonical) {
+        return Some(Err(dbg_erro!("The symbolic link makes a loop", entry.path)));
+      }
+      let entries = match walk_read(&entry.path, self.setup.sort) {
+        Ok(entries) => entries,
+        Err(err) => return Some(Err(dbg_bleb!(err))),
+      };
+      let ignore = match self.setup.ignore_load(&entry.path) {
+        Ok(ignore) => ignore,
+        Err(err) => return Some(Err(dbg_bleb!(err))),
+      };
+      self.frames.push(PathWalkFrame {
+        entries,
+        entry: Some(entry.clone()),
+        canonical,
+        ignore,
+      });
+      if self.setup.post_order {
+        return None;
+      }
+    }
+    if self.setup.accepts(&entry) {
+      return Some(Ok(entry));
+    }
+    None
+  }
+}
+
+struct PathWalkJob {
+  entry: PathEntry,
+  scopes: Vec<std::sync::Arc<(PathBuf, PathIgnore)>>,
+  canonicals: Vec<PathBuf>,
+}
+
+struct PathRunState {
+  queue: Mutex<PathRunQueue>,
+  ready: Condvar,
+}
+
+struct PathRunQueue {
+  jobs: VecDeque<PathWalkJob>,
+  pending: usize,
+  halted: bool,
+}
+
+struct PathRunDone<'a> {
+  state: &'a PathRunState,
+}
+
+impl Drop for PathRunDone<'_> {
+  fn drop(&mut self) {
+    let mut queue = match self.state.queue.lock() {
+      Ok(queue) => queue,
+      Err(err) => err.into_inner(),
+    };
+    queue.pending -= 1;
+    if std::thread::panicking() {
+      queue.halted = true;
+    }
+    drop(queue);
+    self.state.ready.notify_all();
+  }
+}
+
+fn walk_run(
+  setup: &PathWalker,
+  state: &PathRunState,
+  act: &(impl Fn(Result<PathEntry, RubxError>) + Sync),
 ) -> Result<(), RubxError> {
-  for entry in std::fs::read_dir(path).map_err(|err| dbg_erro!(err))? {
-    let entry = entry.map_err(|err| dbg_erro!(err))?;
-    let file_type = entry.file_type().map_err(|err| dbg_erro!(err))?;
-    let inside = format!("{}", entry.path().display());
-    if file_type.is_dir() {
-      path_list_files_exts_in_make(&inside, exts, results).map_err(|err| dbg_erro!(err))?;
+  loop {
+    let job = {
+      let mut queue = state.queue.lock().map_err(|err| dbg_erro!(err))?;
+      loop {
+        if queue.halted {
+          return Ok(());
+        }
+        if let Some(job) = queue.jobs.pop_back() {
+          break job;
+        }
+        if queue.pending == 0 {
+          return Ok(());
+        }
+        queue = state.ready.wait(queue).map_err(|err| dbg_erro!(err))?;
+      }
+    };
+    let done = PathRunDone { state };
+    let mut inners = Vec::new();
+    walk_job(setup, job, act, &mut |inner| inners.push(inner));
+    if !inners.is_empty() {
+      let mut queue = state.queue.lock().map_err(|err| dbg_erro!(err))?;
+      queue.pending += inners.len();
+      queue.jobs.extend(inners);
     }
-    if file_type.is_file() {
-      let name = inside.to_lowercase();
-      for ext in exts {
-        if name.ends_with(&ext.to_lowercase()) {
-          results.push(inside);
-          break;
+    drop(done);
+  }
+}
+
+fn walk_job(
+  setup: &PathWalker,
+  job: PathWalkJob,
+  act: &(impl Fn(Result<PathEntry, RubxError>) + Sync),
+  push: &mut impl FnMut(PathWalkJob),
+) {
+  let mut canonicals = job.canonicals;
+  match setup.canonical(&job.entry) {
+    Ok(Some(canonical)) => {
+      if canonicals.contains(&canonical) {
+        act(Err(dbg_erro!(
+          "The symbolic link makes a loop",
+          job.entry.path
+        )));
+        return;
+      }
+      canonicals.push(canonical);
+    }
+    Ok(None) => {}
+    Err(err) => return act(Err(dbg_bleb!(err))),
+  }
+  let mut scopes = job.scopes;
+  match setup.ignore_load(&job.entry.path) {
+    Ok(ignore) if !ignore.is_empty() => {
+      scopes.push(std::sync::Arc::new((job.entry.path.clone(), ignore)));
+    }
+    Ok(_) => {}
+    Err(err) => return act(Err(dbg_bleb!(err))),
+  }
+  let entries = match walk_read(&job.entry.path, setup.sort) {
+    Ok(entries) => entries,
+    Err(err) => return act(Err(dbg_bleb!(err))),
+  };
+  for inside in entries {
+    let inside = match inside {
+      Ok(inside) => inside,
+      Err(err) => {
+        act(Err(dbg_erro!(err, job.entry.path)));
+        continue;
+      }
+    };
+    let entry = match walk_entry(inside, job.entry.depth + 1, setup.follow_links) {
+      Ok(entry) => entry,
+      Err(err) => {
+        act(Err(dbg_bleb!(err)));
+        continue;
+      }
+    };
+    if setup.skips(&entry)
+      || setup.ignores(
+        &entry,
+        scopes.iter().map(|scope| (scope.0.as_path(), &scope.1)),
+      )
+    {
+      continue;
+    }
+    if setup.accepts(&entry) {
+      act(Ok(entry.clone()));
+    }
+    if setup.descends(&entry) {
+      push(PathWalkJob {
+        entry,
+        scopes: scopes.clone(),
+        canonicals: canonicals.clone(),
+      });
+    }
+  }
+}
+
+fn walk_entry(path: PathBuf, depth: usize, follow_links: bool) -> Result<PathEntry, RubxError> {
+  let link_meta = std::fs::symlink_metadata(&path).map_err(|err| dbg_erro!(err, path))?;
+  let is_symlink = link_meta.file_type().is_symlink();
+  let meta = if follow_links && is_symlink {
+    std::fs::metadata(&path).unwrap_or(link_meta)
+  } else {
+    link_meta
+  };
+  Ok(PathEntry {
+    is_dir: meta.is_dir(),
+    is_file: meta.is_file(),
+    is_symlink,
+    path,
+    depth,
+  })
+}
+
+fn walk_read(
+  path: &Path,
+  sort: bool,
+) -> Result<Box<dyn Iterator<Item = std::io::Result<PathBuf>> + Send>, RubxError> {
+  let entries = std::fs::read_dir(path).map_err(|err| dbg_erro!(err, path))?;
+  if !sort {
+    return Ok(Box::new(
+      entries.map(|entry| entry.map(|entry| entry.path())),
+    ));
+  }
+  let mut paths = entries
+    .map(|entry| entry.map(|entry| entry.path()))
+    .collect::<std::io::Result<Vec<PathBuf>>>()
+    .map_err(|err| dbg_erro!(err, path))?;
+  paths.sort();
+  Ok(Box::new(paths.into_iter().map(Ok)))
+}
+
+pub fn walk(path: &str) -> PathWalker {
+  dbg_call!(path);
+  dbg_reav!(walker_of(PathBuf::from(path)))
+}
+
+fn walker_of(root: PathBuf) -> PathWalker {
+  PathWalker {
+    root,
+    min_depth: 0,
+    max_depth: None,
+    follow_links: false,
+    hidden: true,
+    sort: false,
+    post_order: false,
+    include: None,
+    exclude: None,
+    ignore_files: Vec::new(),
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathIgnore {
+  rules: Vec<PathIgnoreRule>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PathIgnoreRule {
+  segs: Vec<String>,
+  negate: bool,
+  dir_only: bool,
+}
+
+impl PathIgnore {
+  pub fn parse(text: &str) -> PathIgnore {
+    dbg_call!(text);
+    let mut rules = Vec::new();
+    for line in text.lines() {
+      let mut line = line.trim_end_matches('\r');
+      while line.ends_with(' ') && !line.ends_with("\\ ") {
+        line = &line[..line.len() - 1];
+      }
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let negate = line.starts_with('!');
+      if negate || line.starts_with("\\!") || line.starts_with("\\#") {
+        line = &line[1..];
+      }
+      let dir_only = line.ends_with('/');
+      let line = line.trim_end_matches('/');
+      let anchored = line.contains('/');
+      let line = line.trim_start_matches('/');
+      if line.is_empty() {
+        continue;
+      }
+      let mut segs = line.split('/').map(String::from).collect::<Vec<String>>();
+      if !anchored {
+        segs.insert(0, String::from("**"));
+      }
+      dbg_tell!(segs, negate, dir_only);
+      rules.push(PathIgnoreRule {
+        segs,
+        negate,
+        dir_only,
+      });
+    }
+    dbg_reav!(PathIgnore { rules })
+  }
+
+  pub fn load(path: impl AsRef<Path>) -> Result<PathIgnore, RubxError> {
+    let path = path.as_ref();
+    dbg_call!(path);
+    let text = std::fs::read_to_string(path).map_err(|err| dbg_erro!(err, path))?;
+    dbg_reav!(Ok(PathIgnore::parse(&text)))
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  pub fn matched(&self, relative: &str, is_dir: bool) -> Option<bool> {
+    dbg_call!(relative, is_dir);
+    let path_segs = glob_path_segs(relative);
+    dbg_reav!(self
+      .rules
+      .iter()
+      .rev()
+      .find(|rule| {
+        (is_dir || !rule.dir_only) && glob_segs_match(&rule.segs, &path_segs, false, false)
+      })
+      .map(|rule| !rule.negate))
+  }
+
+  pub fn is_ignored(&self, relative: &str, is_dir: bool) -> bool {
+    self.matched(relative, is_dir).unwrap_or(false)
+  }
+}
+
+#[derive(Debug)]
+pub struct PathGlob {
+  patterns: Vec<Vec<String>>,
+  pending: Vec<(PathBuf, Vec<PathBuf>)>,
+  seen: std::collections::HashSet<String>,
+}
+
+impl Iterator for PathGlob {
+  type Item = Result<String, RubxError>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some((entry, ancestors)) = self.pending.pop() {
+      let Some(inside) = text_listed(&entry) else {
+        continue;
+      };
+      let inside_segs = glob_path_segs(&inside);
+      if entry.is_dir()
+        && self
+          .patterns
+          .iter()
+          .any(|pattern| glob_segs_match(pattern, &inside_segs, true, true))
+      {
+        let canonical = match std::fs::canonicalize(&entry) {
+          Ok(canonical) => canonical,
+          Err(err) => return Some(Err(dbg_erro!(err, inside))),
+        };
+        if !ancestors.contains(&canonical) {
+          let mut lineage = ancestors;
+          lineage.push(canonical);
+          match std::fs::read_dir(&entry) {
+            Ok(entries) => {
+              let mut children = Vec::new();
+              for child in entries {
+                match child {
+                  Ok(child) => children.push(child.path()),
+                  Err(err) => return Some(Err(dbg_erro!(err, inside))),
+                }
+              }
+              children.sort();
+              children.reverse();
+              self
+                .pending
+                .extend(children.into_iter().map(|child| (child, lineage.clone())));
+            }
+            Err(err) => return Some(Err(dbg_erro!(err, inside))),
+          }
         }
       }
+      if self
+        .patterns
+        .iter()
+        .any(|pattern| glob_segs_match(pattern, &inside_segs, false, true))
+        && self.seen.insert(inside.clone())
+      {
+        return Some(Ok(inside));
+      }
     }
+    None
   }
-  Ok(())
+}
+
+pub fn glob(pattern: &str) -> Result<PathGlob, RubxError> {
+  dbg_call!(pattern);
+  if pattern.is_empty() {
+    return Err(dbg_erro!("The glob pattern must not be empty"));
+  }
+  let alternatives = glob_braces(pattern);
+  dbg_step!(alternatives);
+  let mut bases: Vec<PathBuf> = Vec::new();
+  let mut patterns = Vec::new();
+  for alternative in &alternatives {
+    let raw_segs = alternative.split('/').collect::<Vec<&str>>();
+    let literals = raw_segs
+      .iter()
+      .take_while(|seg| !glob_has_magic(seg))
+      .count();
+    let base = if literals == raw_segs.len() {
+      alternative.clone()
+    } else if literals == 1 && raw_segs[0].is_empty() {
+      String::from("/")
+    } else {
+      raw_segs[0..literals].join("/")
+    };
+    dbg_tell!(base);
+    if !base.is_empty() {
+      let base = PathBuf::from(base);
+      if base.symlink_metadata().is_ok() && !bases.contains(&base) {
+        bases.push(base);
+      }
+    } else if literals < raw_segs.len() {
+      for entry in std::fs::read_dir(".").map_err(|err| dbg_erro!(err, alternative))? {
+        let entry = entry.map_err(|err| dbg_erro!(err, alternative))?;
+        let entry = PathBuf::from(entry.file_name());
+        if !bases.contains(&entry) {
+          bases.push(entry);
+        }
+      }
+    }
+    patterns.push(glob_pattern_segs(alternative));
+  }
+  bases.sort();
+  bases.reverse();
+  dbg_reav!(Ok(PathGlob {
+    patterns,
+    pending: bases.into_iter().map(|base| (base, Vec::new())).collect(),
+    seen: std::collections::HashSet::new(),
+  }))
+}
+
+pub fn matches(pattern: &str, path: &str) -> bool {
+  dbg_call!(pattern, path);
+  let path_segs = glob_path_segs(path);
+  dbg_reav!(glob_braces(pattern).iter().any(|alternative| {
+    glob_segs_match(&glob_pattern_segs(alternative), &path_segs, false, true)
+  }))
+}
+
+fn glob_has_magic(seg: &str) -> bool {
+  seg.contains(['*', '?', '[', '\\'])
+}
+
+fn glob_pattern_segs(pattern: &str) -> Vec<String> {
+  glob_segs_split(pattern.split('/'))
+}
+
+fn glob_path_segs(path: &str) -> Vec<String> {
+  let is_win = crate::rux_fires::is_win();
+  glob_segs_split(path.split(|ch| ch == '/' || (is_win && ch == '\\')))
+}
+
+fn glob_segs_split<'a>(segs: impl Iterator<Item = &'a str>) -> Vec<String> {
+  let mut results = Vec::new();
+  for (index, seg) in segs.enumerate() {
+    if (seg.is_empty() && index > 0) || seg == "." {
+      continue;
+    }
+    results.push(seg.to_string());
+  }
+  results
+}
+
+fn glob_braces(pattern: &str) -> Vec<String> {
+  let chars = pattern.chars().collect::<Vec<char>>();
+  let mut index = 0;
+  while index < chars.len() {
+    match chars[index] {
+      '\\' => index += 1,
+      '{' => {
+        let mut depth = 0;
+        let mut splits = Vec::new();
+        let mut closer = None;
+        let mut inner = index + 1;
+        while inner < chars.len() {
+          match chars[inner] {
+            '\\' => inner += 1,
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+              closer = Some(inner);
+              break;
+            }
+            '}' => depth -= 1,
+            ',' if depth == 0 => splits.push(inner),
+            _ => {}
+          }
+          inner += 1;
+        }
+        if let Some(closer) = closer {
+          let prefix = chars[0..index].iter().collect::<String>();
+          let suffix = chars[closer + 1..].iter().collect::<String>();
+          let mut starts = vec![index + 1];
+          starts.extend(splits.iter().map(|split| split + 1));
+          let mut ends = splits.clone();
+          ends.push(closer);
+          let mut results = Vec::new();
+          for (start, end) in starts.into_iter().zip(ends) {
+            let choice = chars[start..end].iter().collect::<String>();
+            for expanded in glob_braces(&format!("{}{}{}", prefix, choice, suffix)) {
+              results.push(expanded);
+            }
+          }
+          return results;
+        }
+      }
+      _ => {}
+    }
+    index += 1;
+  }
+  vec![String::from(pattern)]
+}
+
+fn glob_segs_match(pattern: &[String], path: &[String], partial: bool, dots: bool) -> bool {
+  if path.is_empty() {
+    return partial || pattern.iter().all(|seg| seg == "**");
+  }
+  if pattern.is_empty() {
+    return false;
+  }
+  let hidden = dots && path[0].starts_with('.');
+  if pattern[0] == "**" {
+    return glob_segs_match(&pattern[1..], path, partial, dots)
+      || (!hidden && glob_segs_match(pattern, &path[1..], partial, dots));
+  }
+  if hidden && !pattern[0].starts_with('.') {
+    return false;
+  }
+  glob_seg_match(
+    &pattern[0].chars().collect::<Vec<char>>(),
+    &path[0].chars().collect::<Vec<char>>(),
+  ) && glob_segs_match(&pattern[1..], &path[1..], partial, dots)
+}
+
+fn glob_seg_match(pattern: &[char], name: &[char]) -> bool {
+  let (mut pat_index, mut name_index) = (0, 0);
+  let mut backtrack: Option<(usize, usize)> = None;
+  while name_index < name.len() {
+    let mut advance = None;
+    if pat_index < pattern.len() {
+      match pattern[pat_index] {
+        '*' => {
+          backtrack = Some((pat_index, name_index));
+          pat_index += 1;
+          continue;
+        }
+        '?' => advance = Some(1),
+        '[' => {
+          if let Some((matched, size)) =
+            glob_class_match(&pattern[pat_index..], name[name_index])
+          {
+            if matched {
+              advance = Some(size);
+            }
+          } else if name[name_index] == '[' {
+            advance = Some(1);
+          }
+        }
+        '\\' if pat_index + 1 < pattern.len() => {
+          if pattern[pat_index + 1] == name[name_index] {
+            advance = Some(2);
+          }
+        }
+        ch => {
+          if ch == name[name_index] {
+            advance = Some(1);
+          }
+        }
+      }
+    }
+    if let Some(size) = advance {
+      pat_index += size;
+      name_index += 1;
+    } else if let Some((star_index, star_name)) = backtrack {
+      pat_index = star_index + 1;
+      name_index = star_name + 1;
+      backtrack = Some((star_index, star_name + 1));
+    } else {
+      return false;
+    }
+  }
+  pattern[pat_index..].iter().all(|ch| *ch == '*')
+}
+
+fn glob_class_match(class: &[char], ch: char) -> Option<(bool, usize)> {
+  let mut index = 1;
+  let negate = index < class.len() && (class[index] == '!' || class[index] == '^');
+  if negate {
+    index += 1;
+  }
+  let mut matched = false;
+  let mut first = true;
+  while index < class.len() {
+    if class[index] == ']' && !first {
+      return Some((matched != negate, index + 1));
+    }
+    first = false;
+    let low = class[index];
+    if index + 2 < class.len() && class[index + 1] == '-' && class[index + 2] != ']' {
+      if low <= ch && ch <= class[index + 2] {
+        matched = true;
+      }
+      index += 3;
+    } else {
+      if low == ch {
+        matched = true;
+      }
+      index += 1;
+    }
+  }
+  None
 }
 
 pub fn path_env() -> Result<String, RubxError> {
@@ -628,13 +2559,8 @@ pub fn path_env_dirs() -> Result<Vec<String>, RubxError> {
 }
 
 pub fn traverse<F: FnMut(&PathBuf)>(path: &PathBuf, act: &mut F) -> RubxResult<()> {
-  if path.is_dir() {
-    for inside in std::fs::read_dir(&path)? {
-      let inside = inside?;
-      let inside = inside.path();
-      traverse(&inside, act)?;
-    }
+  for entry in walker_of(path.clone()).follow_links(true).post_order(true) {
+    act(&entry?.path);
   }
-  act(&path);
   Ok(())
 }
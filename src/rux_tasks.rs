@@ -0,0 +1,439 @@
+use chrono::offset::{Local, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike};
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::rux_debug::{dbg_bleb, dbg_erro, dbg_info, dbg_warn};
+use crate::rux_debug::{dbg_call, dbg_reav, dbg_step, dbg_tell};
+use crate::rux_fires;
+use crate::rux_rands;
+use crate::RubxError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cron {
+  minutes: Vec<bool>,
+  hours: Vec<bool>,
+  days: Vec<bool>,
+  months: Vec<bool>,
+  weekdays: Vec<bool>,
+  any_day: bool,
+  any_weekday: bool,
+}
+
+impl Cron {
+  pub fn parse(expression: &str) -> Result<Cron, RubxError> {
+    dbg_call!(expression);
+    let expression = match expression.trim() {
+      "@yearly" | "@annually" => "0 0 1 1 *",
+      "@monthly" => "0 0 1 * *",
+      "@weekly" => "0 0 * * 0",
+      "@daily" | "@midnight" => "0 0 * * *",
+      "@hourly" => "0 * * * *",
+      other => other,
+    };
+    let fields = expression.split_whitespace().collect::<Vec<&str>>();
+    if fields.len() != 5 {
+      return Err(dbg_erro!(
+        "The cron expression must have five fields",
+        expression
+      ));
+    }
+    let mut weekdays = cron_field(fields[4], 0, 7).map_err(|err| dbg_bleb!(err))?;
+    if weekdays[7] {
+      weekdays[0] = true;
+    }
+    weekdays.truncate(7);
+    dbg_reav!(Ok(Cron {
+      minutes: cron_field(fields[0], 0, 59).map_err(|err| dbg_bleb!(err))?,
+      hours: cron_field(fields[1], 0, 23).map_err(|err| dbg_bleb!(err))?,
+      days: cron_field(fields[2], 1, 31).map_err(|err| dbg_bleb!(err))?,
+      months: cron_field(fields[3], 1, 12).map_err(|err| dbg_bleb!(err))?,
+      weekdays,
+      any_day: fields[2].starts_with('*'),
+      any_weekday: fields[4].starts_with('*'),
+    }))
+  }
+
+  pub fn matches<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> bool {
+    dbg_call!(time);
+    dbg_reav!(
+      self.minutes[time.minute() as usize]
+        && self.hours[time.hour() as usize]
+        && self.months[time.month() as usize]
+        && self.matches_day(time)
+    )
+  }
+
+  fn matches_day<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> bool {
+    let day = self.days[time.day() as usize];
+    let weekday = self.weekdays[time.weekday().num_days_from_sunday() as usize];
+    if self.any_day || self.any_weekday {
+      day && weekday
+    } else {
+      day || weekday
+    }
+  }
+
+  pub fn next_after<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    dbg_call!(time);
+    let zone = time.timezone();
+    let mut next =
+      time.clone().with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+    let limit = next.year() + 5;
+    while next.year() <= limit {
+      if !self.months[next.month() as usize] {
+        let (year, month) = if next.month() == 12 {
+          (next.year() + 1, 1)
+        } else {
+          (next.year(), next.month() + 1)
+        };
+        next = cron_time(&zone, year, month, 1, 0, 0)?;
+        continue;
+      }
+      if !self.matches_day(&next) {
+        let day = next.naive_local().date().succ_opt()?;
+        next = cron_time(&zone, day.year(), day.month(), day.day(), 0, 0)?;
+        continue;
+      }
+      if !self.hours[next.hour() as usize] {
+        next = next.with_minute(0)? + chrono::Duration::hours(1);
+        continue;
+      }
+      if !self.minutes[next.minute() as usize] {
+        next += chrono::Duration::minutes(1);
+        continue;
+      }
+      dbg_reav!(Some(next));
+    }
+    dbg_reav!(None)
+  }
+}
+
+fn cron_field(field: &str, min: usize, max: usize) -> Result<Vec<bool>, RubxError> {
+  let mut result = vec![false; max + 1];
+  for part in field.split(',') {
+    let (range, step) = match part.find('/') {
+      Some(pos) => (
+        &part[..pos],
+        part[pos + 1..]
+          .parse::<usize>()
+          .map_err(|err| dbg_erro!(err, field))?,
+      ),
+      None => (part, 1),
+    };
+    if step == 0 {
+      return Err(dbg_erro!("The cron step must be greater than zero", field));
+    }
+    let (start, end) = if range == "*" {
+      (min, max)
+    } else if let Some(pos) = range.find('-') {
+      (
+        range[..pos]
+          .parse::<usize>()
+          .map_err(|err| dbg_erro!(err, field))?,
+        range[pos + 1..]
+          .parse::<usize>()
+          .map_err(|err| dbg_erro!(err, field))?,
+      )
+    } else {
+      let start = range
+        .parse::<usize>()
+        .map_err(|err| dbg_erro!(err, field))?;
+      (start, if part.contains('/') { max } else { start })
+    };
+    if start < min || end > max || start > end {
+      return Err(dbg_erro!("The cron field is out of range", field, min, max));
+    }
+    for value in (start..=end).step_by(step) {
+      result[value] = true;
+    }
+  }
+  Ok(result)
+}
+
+fn cron_time<Tz: TimeZone>(
+  zone: &Tz,
+  year: i32,
+  month: u32,
+  day: u32,
+  hour: u32,
+  minute: u32,
+) -> Option<DateTime<Tz>> {
+  let naive = NaiveDate::from_ymd_opt(year, month, day)?.and_hms_opt(hour, minute, 0)?;
+  zone.from_local_datetime(&naive).earliest().or_else(|| {
+    zone
+      .from_local_datetime(&(naive + chrono::Duration::hours(1)))
+      .earliest()
+  })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaskWhen {
+  Every(Duration),
+  Cron(Cron),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskMissed {
+  Skip,
+  RunOnce,
+  RunAll,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskSetup {
+  pub when: TaskWhen,
+  pub missed: TaskMissed,
+  pub jitter: u32,
+  pub run_now: bool,
+  pub utc: bool,
+  pub shutdown: bool,
+}
+
+impl TaskSetup {
+  pub fn every(millis: u64) -> TaskSetup {
+    dbg_call!(millis);
+    dbg_reav!(TaskSetup {
+      when: TaskWhen::Every(Duration::from_millis(millis)),
+      missed: TaskMissed::Skip,
+      jitter: 0,
+      run_now: false,
+      utc: false,
+      shutdown: true,
+    })
+  }
+
+  pub fn cron(expression: &str) -> Result<TaskSetup, RubxError> {
+    dbg_call!(expression);
+    dbg_reav!(Ok(TaskSetup {
+      when: TaskWhen::Cron(Cron::parse(expression).map_err(|err| dbg_bleb!(err))?),
+      missed: TaskMissed::Skip,
+      jitter: 0,
+      run_now: false,
+      utc: false,
+      shutdown: true,
+    }))
+  }
+
+  fn next_after(&self, time: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+    match &self.when {
+      TaskWhen::Every(every) => Some(*time + chrono::Duration::from_std(*every).ok()?),
+      TaskWhen::Cron(cron) if self.utc => cron.next_after(time),
+      TaskWhen::Cron(cron) => cron
+        .next_after(&time.with_timezone(&Local))
+        .map(|next| next.with_timezone(&Utc)),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct TaskHandle {
+  name: String,
+  stop: Arc<AtomicBool>,
+  runs: Arc<AtomicUsize>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl TaskHandle {
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn runs(&self) -> usize {
+    dbg_call!();
+    dbg_reav!(self.runs.load(Ordering::Acquire))
+  }
+
+  pub fn is_running(&self) -> bool {
+    dbg_call!();
+    dbg_reav!(match &self.thread {
+      Some(thread) => !thread.is_finished(),
+      None => false,
+    })
+  }
+
+  pub fn stop(&self) {
+    dbg_call!();
+    self.stop.store(true, Ordering::Release);
+  }
+
+  pub fn join(mut self) -> Result<(), RubxError> {
+    dbg_call!();
+    self.stop();
+    if let Some(thread) = self.thread.take() {
+      thread
+        .join()
+        .map_err(|err| dbg_erro!(format!("{:?}", err), self.name))?;
+    }
+    Ok(())
+  }
+}
+
+impl Drop for TaskHandle {
+  fn drop(&mut self) {
+    self.stop();
+  }
+}
+
+pub fn every(
+  name: &str,
+  millis: u64,
+  job: impl FnMut() -> Result<(), RubxError> + Send + 'static,
+) -> Result<TaskHandle, RubxError> {
+  dbg_call!(name, millis);
+  dbg_reav!(schedule(name, TaskSetup::every(millis), job).map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn cron(
+  name: &str,
+  expression: &str,
+  job: impl FnMut() -> Result<(), RubxError> + Send + 'static,
+) -> Result<TaskHandle, RubxError> {
+  dbg_call!(name, expression);
+  let setup = TaskSetup::cron(expression).map_err(|err| dbg_bleb!(err))?;
+  dbg_reav!(schedule(name, setup, job).map_err(|err| dbg_bleb!(err)))
+}
+
+pub fn schedule_cmd(
+  name: &str,
+  setup: TaskSetup,
+  command: &str,
+  args: &[impl AsRef<str>],
+  dir: Option<impl AsRef<str>>,
+) -> Result<TaskHandle, RubxError> {
+  dbg_call!(name, setup, command);
+  let command = String::from(command);
+  let args = args
+    .iter()
+    .map(|arg| String::from(arg.as_ref()))
+    .collect::<Vec<String>>();
+  let dir = dir.map(|dir| String::from(dir.as_ref()));
+  dbg_reav!(schedule(name, setup, move || {
+    rux_fires::cmd(&command, &args, dir.as_ref(), Some(true), Some(true))
+      .map_err(|err| dbg_bleb!(err))?;
+    Ok(())
+  }))
+}
+
+pub fn schedule(
+  name: &str,
+  setup: TaskSetup,
+  mut job: impl FnMut() -> Result<(), RubxError> + Send + 'static,
+) -> Result<TaskHandle, RubxError> {
+  dbg_call!(name, setup);
+  if setup.when == TaskWhen::Every(Duration::ZERO) {
+    return Err(dbg_erro!(
+      "The task interval must be greater than zero",
+      name
+    ));
+  }
+  let stop = Arc::new(AtomicBool::new(false));
+  let runs = Arc::new(AtomicUsize::new(0));
+  let link_name = String::from(name);
+  let link_stop = stop.clone();
+  let link_runs = runs.clone();
+  let thread = std::thread::Builder::new()
+    .name(format!("rubx-task-{}", name))
+    .spawn(move || {
+      let name = link_name;
+      let now = Utc::now();
+      let mut due = if setup.run_now {
+        Some(now)
+      } else {
+        setup.next_after(&now)
+      };
+      dbg_info!("Task scheduled", name, due);
+      while let Some(this_due) = due {
+        let delay = if setup.jitter > 0 {
+          rux_rands::range(0, setup.jitter.saturating_add(1)) as i64
+        } else {
+          0
+        };
+        let this_wait = this_due + chrono::Duration::milliseconds(delay);
+        if !wait_until(this_wait, &link_stop, setup.shutdown) {
+          break;
+        }
+        dbg_step!(name, this_due);
+        match job() {
+          Ok(()) => {
+            dbg_info!("Task run finished", name, this_due);
+          }
+          Err(err) => {
+            dbg_bleb!(err, name, this_due);
+          }
+        }
+        link_runs.fetch_add(1, Ordering::AcqRel);
+        due = next_due(&setup, &this_due, &name);
+      }
+      dbg_info!("Task stopped", name);
+    })
+    .map_err(|err| dbg_erro!(err, name))?;
+  dbg_reav!(Ok(TaskHandle {
+    name: String::from(name),
+    stop,
+    runs,
+    thread: Some(thread),
+  }))
+}
+
+fn next_due(setup: &TaskSetup, last_due: &DateTime<Utc>, name: &str) -> Option<DateTime<Utc>> {
+  let now = Utc::now();
+  let mut next = setup.next_after(last_due)?;
+  if next >= now {
+    return Some(next);
+  }
+  let mut missed = 0;
+  if let TaskWhen::Every(every) = &setup.when {
+    let every = every.as_nanos();
+    let passed = (now - *last_due).to_std().ok()?.as_nanos();
+    let ticks = match setup.missed {
+      TaskMissed::RunAll => 1,
+      TaskMissed::RunOnce => passed.div_ceil(every) - 1,
+      TaskMissed::Skip => passed.div_ceil(every),
+    };
+    missed = ticks - 1;
+    let offset = Duration::from_nanos(u64::try_from(every * ticks).ok()?);
+    next = *last_due + chrono::Duration::from_std(offset).ok()?;
+  } else {
+    match setup.missed {
+      TaskMissed::RunAll => {}
+      TaskMissed::RunOnce => {
+        while let Some(after) = setup.next_after(&next) {
+          if after >= now {
+            break;
+          }
+          missed += 1;
+          next = after;
+        }
+      }
+      TaskMissed::Skip => {
+        while next < now {
+          missed += 1;
+          next = setup.next_after(&next)?;
+        }
+      }
+    }
+  }
+  dbg_tell!(missed);
+  if missed > 0 {
+    dbg_warn!("Task missed runs", name, missed, setup.missed);
+  }
+  Some(next)
+}
+
+fn wait_until(due: DateTime<Utc>, stop: &AtomicBool, shutdown: bool) -> bool {
+  loop {
+    if stop.load(Ordering::Acquire) || (shutdown && rux_fires::is_shutdown()) {
+      return false;
+    }
+    let remaining = due - Utc::now();
+    if remaining <= chrono::Duration::zero() {
+      return true;
+    }
+    let remaining = remaining.to_std().unwrap_or_default();
+    std::thread::sleep(remaining.min(Duration::from_millis(100)));
+  }
+}
@@ -1,13 +1,16 @@
+use once_cell::sync::Lazy;
 use regex::Regex;
 
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
-use std::sync::{Arc, Mutex};
-use std::thread::JoinHandle;
+use std::iter::Peekable;
+use std::str::Chars;
 
-use crate::rux_debug::dbg_erro;
+use crate::rux_debug::{dbg_bleb, dbg_erro};
 use crate::rux_debug::{dbg_call, dbg_reav, dbg_tell};
+use crate::rux_fires;
+use crate::rux_paths;
 use crate::{RubxError, RubxResult};
 
 pub static LINE_SPACE_CHARS: &[char] = &[' ', '\t'];
@@ -240,6 +243,169 @@ pub fn split_spaces(text: &str) -> Vec<String> {
     .collect()
 }
 
+pub fn split_words(text: &str, expand: Option<bool>) -> Result<Vec<String>, RubxError> {
+  dbg_call!(text, expand);
+  let expand = expand.unwrap_or(false);
+  let mut results = Vec::new();
+  let mut actual = String::new();
+  let mut has_word = false;
+  let mut chars = text.chars().peekable();
+  while let Some(ch) = chars.next() {
+    match ch {
+      '\'' => {
+        has_word = true;
+        loop {
+          match chars.next() {
+            Some('\'') => break,
+            Some(ch) => actual.push(ch),
+            None => return Err(dbg_erro!("Unterminated single quote", text)),
+          }
+        }
+      }
+      '"' => {
+        has_word = true;
+        loop {
+          match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+              Some(ch) if ch == '"' || ch == '\\' || ch == '$' || ch == '`' => actual.push(ch),
+              Some('\n') => {}
+              Some(ch) => {
+                actual.push('\\');
+                actual.push(ch);
+              }
+              None => return Err(dbg_erro!("Unterminated double quote", text)),
+            },
+            Some('$') if expand => {
+              split_words_expand(&mut chars, &mut actual).map_err(|err| dbg_bleb!(err, text))?
+            }
+            Some(ch) => actual.push(ch),
+            None => return Err(dbg_erro!("Unterminated double quote", text)),
+          }
+        }
+      }
+      '\\' => {
+        has_word = true;
+        match chars.next() {
+          Some('\n') => {}
+          Some(ch) => actual.push(ch),
+          None => return Err(dbg_erro!("Unterminated escape", text)),
+        }
+      }
+      '$' if expand => {
+        has_word = true;
+        split_words_expand(&mut chars, &mut actual).map_err(|err| dbg_bleb!(err, text))?;
+      }
+      ch if ch.is_whitespace() => {
+        if has_word {
+          dbg_tell!(actual);
+          results.push(actual.clone());
+          actual.clear();
+          has_word = false;
+        }
+      }
+      ch => {
+        has_word = true;
+        actual.push(ch);
+      }
+    }
+  }
+  if has_word {
+    results.push(actual);
+  }
+  dbg_reav!(Ok(results))
+}
+
+pub(crate) fn split_words_expand(
+  chars: &mut Peekable<Chars>,
+  actual: &mut String,
+) -> Result<(), RubxError> {
+  let mut name = String::new();
+  if chars.peek() == Some(&'{') {
+    chars.next();
+    let mut depth = 1;
+    loop {
+      match chars.next() {
+        Some('{') => {
+          depth += 1;
+          name.push('{');
+        }
+        Some('}') => {
+          depth -= 1;
+          if depth == 0 {
+            break;
+          }
+          name.push('}');
+        }
+        Some(ch) => name.push(ch),
+        None => return Err(dbg_erro!("Unterminated variable expansion", name)),
+      }
+    }
+    let (name, default) = match name.find(":-") {
+      Some(pos) => (&name[..pos], Some(&name[pos + 2..])),
+      None => (&name[..], None),
+    };
+    match std::env::var(name) {
+      Ok(value) if !value.is_empty() || default.is_none() => actual.push_str(&value),
+      _ => {
+        if let Some(default) = default {
+          actual.push_str(&split_words_expand_all(default).map_err(|err| dbg_bleb!(err))?);
+        }
+      }
+    }
+    return Ok(());
+  }
+  while let Some(ch) = chars.peek() {
+    if !(ch.is_ascii_alphanumeric() || *ch == '_') {
+      break;
+    }
+    name.push(*ch);
+    chars.next();
+  }
+  if name.is_empty() {
+    actual.push('$');
+  } else {
+    actual.push_str(&std::env::var(&name).unwrap_or_default());
+  }
+  Ok(())
+}
+
+pub(crate) fn split_words_expand_all(text: &str) -> Result<String, RubxError> {
+  let mut result = String::new();
+  let mut chars = text.chars().peekable();
+  while let Some(ch) = chars.next() {
+    if ch == '$' {
+      split_words_expand(&mut chars, &mut result).map_err(|err| dbg_bleb!(err, text))?;
+    } else {
+      result.push(ch);
+    }
+  }
+  Ok(result)
+}
+
+pub fn quote_word(word: &str) -> String {
+  dbg_call!(word);
+  if word.is_empty() {
+    dbg_reav!(String::from("''"));
+  }
+  if word
+    .chars()
+    .all(|ch| ch.is_ascii_alphanumeric() || "-_=+/.,:@%^".contains(ch))
+  {
+    dbg_reav!(String::from(word));
+  }
+  dbg_reav!(format!("'{}'", word.replace('\'', "'\\''")))
+}
+
+pub fn join_words(words: &[impl AsRef<str> + std::fmt::Debug]) -> String {
+  dbg_call!(words);
+  dbg_reav!(words
+    .iter()
+    .map(|word| quote_word(word.as_ref()))
+    .collect::<Vec<String>>()
+    .join(" "))
+}
+
 pub fn text_file_find(path: &str, content: String) -> Result<Option<Vec<String>>, RubxError> {
   dbg_call!(path, content);
   text_file_find_any(path, vec![content])
@@ -297,67 +463,26 @@ pub fn text_files_find(
   text_files_find_any(paths, vec![content])
 }
 
+static FIND_POOL: Lazy<Option<rux_fires::FirePool>> = Lazy::new(|| rux_fires::pool(None).ok());
+
 pub fn text_files_find_any(
   paths: Vec<String>,
   contents: Vec<String>,
 ) -> Result<Option<Vec<String>>, RubxError> {
   dbg_call!(paths, contents);
-  let cpus = num_cpus::get();
-  let pool = Arc::new(Mutex::new(paths));
-  let mut handles: Vec<JoinHandle<Option<Vec<String>>>> = Vec::with_capacity(cpus);
-  for _ in 0..cpus {
-    let link_pool = pool.clone();
-    let link_contents = contents.clone();
-    let handle = std::thread::spawn(move || -> Option<Vec<String>> {
-      let mut partial: Option<Vec<String>> = None;
-      loop {
-        let path = {
-          let mut lock_pool = link_pool.lock().map_err(|err| dbg_erro!(err)).unwrap();
-          lock_pool.pop()
-        };
-        if path.is_none() {
-          break;
-        }
-        let path = path.unwrap();
-        let file_founds = text_file_find_any(&path, link_contents.clone())
-          .map_err(|err| dbg_erro!(err))
-          .unwrap();
-        if let Some(file_founds) = file_founds {
-          if partial.is_none() {
-            partial = Some(Vec::new());
-          }
-          let edit_partial = partial
-            .as_mut()
-            .ok_or("Could not get partial results as mutable")
-            .map_err(|err| dbg_erro!(err))
-            .unwrap();
-          for found in file_founds {
-            edit_partial.push(found);
-          }
-        }
-      }
-      partial
-    });
-    handles.push(handle);
-  }
+  let partials = match FIND_POOL.as_ref() {
+    Some(pool) if paths.len() >= 4 => pool
+      .map(paths, |path| text_file_find_any(&path, contents.clone()))
+      .map_err(|err| dbg_bleb!(err))?,
+    _ => paths
+      .iter()
+      .map(|path| text_file_find_any(path, contents.clone()))
+      .collect(),
+  };
   let mut results: Option<Vec<String>> = None;
-  for handle in handles {
-    let partial = match handle.join() {
-      Ok(partial) => partial,
-      Err(error) => return Err(dbg_erro!(format!("{:?}", error))),
-    };
-    if let Some(partial) = partial {
-      if results.is_none() {
-        results = Some(Vec::new());
-      }
-      let editor = results
-        .as_mut()
-        .ok_or("Could not get results as mutable")
-        .map_err(|err| dbg_erro!(err))
-        .unwrap();
-      for found in partial {
-        editor.push(found);
-      }
+  for partial in partials {
+    if let Some(partial) = partial.map_err(|err| dbg_bleb!(err))? {
+      results.get_or_insert_with(Vec::new).extend(partial);
     }
   }
   Ok(results)
@@ -423,6 +548,11 @@ pub fn write(path: &str, contents: String) -> Result<(), RubxError> {
   Ok(write!(file, "{}", contents).map_err(|err| dbg_erro!(err, path))?)
 }
 
+pub fn write_atomic(path: &str, contents: String) -> Result<(), RubxError> {
+  dbg_call!(path, contents);
+  rux_paths::write_atomic(path, contents).map_err(|err| dbg_bleb!(err))
+}
+
 pub fn write_lines(path: &str, lines: Vec<String>) -> Result<(), RubxError> {
   dbg_call!(path, lines);
   let mut file = std::fs::OpenOptions::new()
@@ -438,6 +568,17 @@ pub fn write_lines(path: &str, lines: Vec<String>) -> Result<(), RubxError> {
   Ok(())
 }
 
+pub fn write_lines_atomic(path: &str, lines: Vec<String>) -> Result<(), RubxError> {
+  dbg_call!(path, lines);
+  rux_paths::write_atomic_with(path, |file| {
+    for line in &lines {
+      writeln!(file, "{}", line).map_err(|err| dbg_erro!(err, path, line))?;
+    }
+    Ok(())
+  })
+  .map_err(|err| dbg_bleb!(err))
+}
+
 pub fn write_inputs(path: &str) -> Result<(), RubxError> {
   dbg_call!(path);
   let mut file = std::fs::OpenOptions::new()
@@ -0,0 +1,383 @@
+#[test]
+fn cmds_test() {
+    use crate::rux_fires;
+    let commands = vec![
+        vec!["echo".to_string(), "first".to_string()],
+        vec!["false".to_string()],
+        vec!["echo".to_string(), "third".to_string()],
+    ];
+    let results = rux_fires::cmds(commands, None::<&str>, Some(2), Some(false), Some(false)).unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().1, "first");
+    assert!(results[1].is_err());
+    assert_eq!(results[2].as_ref().unwrap().1, "third");
+}
+
+#[test]
+fn spawn_test() {
+    use crate::rux_fires;
+    let mut child = rux_fires::spawn("echo", &["spawned"], None::<&str>, Some(false)).unwrap();
+    assert!(child.pid() > 0);
+    let (code, output) = child.wait().unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(output, "spawned");
+    let mut child = rux_fires::spawn("sleep", &["5"], None::<&str>, Some(false)).unwrap();
+    assert!(child.is_running().unwrap());
+    child.kill().unwrap();
+    let (code, _) = child.wait().unwrap();
+    assert_ne!(code, 0);
+    assert!(!child.is_running().unwrap());
+    let child = rux_fires::spawn("sleep", &["0.1"], None::<&str>, Some(false)).unwrap();
+    let pid = child.pid();
+    drop(child);
+    for _ in 0..100 {
+        if !rux_fires::running_pids().contains(&pid) {
+            break;
+        }
+        rux_fires::sleep(10);
+    }
+    assert!(!rux_fires::running_pids().contains(&pid));
+    #[cfg(target_os = "linux")]
+    assert!(!std::path::Path::new(&format!("/proc/{}", pid)).exists());
+}
+
+#[test]
+fn spawn_managed_test() {
+    use crate::rux_fires;
+    let mut child = rux_fires::spawn_managed("sleep", &["5"], None::<&str>, Some(false)).unwrap();
+    let waiter = std::thread::spawn(move || child.wait().unwrap());
+    rux_fires::sleep(100);
+    assert_eq!(rux_fires::managed_kill_all().unwrap(), 1);
+    let (code, _) = waiter.join().unwrap();
+    assert_ne!(code, 0);
+    assert_eq!(rux_fires::managed_kill_all().unwrap(), 0);
+    #[cfg(unix)]
+    rux_fires::managed_kill_at_exit().unwrap();
+    #[cfg(not(unix))]
+    assert!(rux_fires::managed_kill_at_exit().is_err());
+}
+
+#[test]
+fn which_test() {
+    use crate::rux_fires;
+    let found = rux_fires::which("sh").unwrap().unwrap();
+    assert!(rux_fires::is_executable(&found));
+    assert!(rux_fires::which_all("sh").unwrap().contains(&found));
+    assert!(rux_fires::which("rubx-not-a-program").unwrap().is_none());
+    let result = rux_fires::cmd("rubx-not-a-program", &[] as &[&str], None::<&str>, None, None);
+    assert!(format!("{}", result.unwrap_err()).contains("Program not found"));
+}
+
+#[test]
+fn cmd_line_test() {
+    use crate::rux_fires;
+    let (_, output) =
+        rux_fires::cmd_line("echo 'one  two'", None, None::<&str>, Some(false), None).unwrap();
+    assert_eq!(output, "one  two");
+    let (_, output) =
+        rux_fires::cmd_shell("echo first && echo second", None::<&str>, Some(false), None).unwrap();
+    assert_eq!(output, "first\nsecond");
+}
+
+#[test]
+fn cmd_retry_test() {
+    use crate::rux_fires;
+    let retry = rux_fires::CmdRetry {
+        attempts: 3,
+        delay: 10,
+        jitter: 0,
+        on_codes: vec![3],
+        ..Default::default()
+    };
+    assert!(retry.should_retry(3, ""));
+    assert!(!retry.should_retry(4, ""));
+    assert_eq!(retry.delay_for(1), 10);
+    assert_eq!(retry.delay_for(3), 40);
+    let (code, _) = rux_fires::cmd_retry(
+        "sh",
+        &["-c", "exit 3"],
+        None::<&str>,
+        Some(false),
+        Some(false),
+        &retry,
+    )
+    .unwrap();
+    assert_eq!(code, 3);
+}
+
+#[test]
+fn prompt_test() {
+    use crate::rux_fires;
+    let mut guard = rux_fires::env_guard();
+    guard.set("CI", "true");
+    assert!(!rux_fires::is_interactive());
+    assert_eq!(rux_fires::prompt("Name?", Some("rubx"), Some(1)).unwrap(), "rubx");
+    assert_eq!(rux_fires::pause_key(Some(1)).unwrap(), None);
+    rux_fires::pause_for(None).unwrap();
+}
+
+#[test]
+fn env_test() {
+    use crate::rux_fires;
+    {
+        let mut guard = rux_fires::env_guard();
+        guard.set("RUBX_ENV_TEST_NAME", "rubx");
+        guard.set("RUBX_ENV_TEST_BOOL", "yes");
+        guard.set("RUBX_ENV_TEST_INT", "42");
+        guard.set_list("RUBX_ENV_TEST_LIST", &["a", "b"]);
+        assert!(rux_fires::env_get_bool("RUBX_ENV_TEST_BOOL"));
+        assert_eq!(rux_fires::env_get_int("RUBX_ENV_TEST_INT").unwrap(), Some(42));
+        assert_eq!(rux_fires::env_get_list("RUBX_ENV_TEST_LIST"), vec!["a", "b"]);
+        let expanded = rux_fires::env_expand(
+            "$RUBX_ENV_TEST_NAME-${RUBX_ENV_TEST_NAME}-${RUBX_ENV_TEST_NONE:-${RUBX_ENV_TEST_INT}}",
+        )
+        .unwrap();
+        assert_eq!(expanded, "rubx-rubx-42");
+        assert!(rux_fires::env_expand("${RUBX_ENV_TEST_NAME").is_err());
+        let path = std::env::temp_dir().join("rubx_env_test.env");
+        let path = format!("{}", path.display());
+        std::fs::write(
+            &path,
+            "# comment\nexport RUBX_ENV_TEST_LOAD=\"${RUBX_ENV_TEST_NAME} loaded\"\nRUBX_ENV_TEST_NAME=other\n",
+        )
+        .unwrap();
+        assert_eq!(guard.load(&path, None).unwrap(), 1);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(rux_fires::env_get("RUBX_ENV_TEST_LOAD").unwrap(), "rubx loaded");
+        assert_eq!(rux_fires::env_get("RUBX_ENV_TEST_NAME").unwrap(), "rubx");
+    }
+    assert!(rux_fires::env_get("RUBX_ENV_TEST_NAME").is_none());
+    assert!(rux_fires::env_get("RUBX_ENV_TEST_LOAD").is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_test() {
+    let temp = std::env::temp_dir().join("rubx_signal_test.tmp");
+    std::fs::write(&temp, "clean").unwrap();
+    let status = run_child("tests::fires::signal_child", &[("RUBX_SIGNAL_TEMP", &temp)]);
+    assert_eq!(status.code(), Some(0));
+    assert!(!temp.exists());
+}
+
+#[cfg(unix)]
+#[test]
+#[ignore]
+fn signal_child() {
+    use crate::rux_fires;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::Arc;
+    let temp = match std::env::var("RUBX_SIGNAL_TEMP") {
+        Ok(temp) => temp,
+        Err(_) => return,
+    };
+    rux_fires::set_signal_forward(false);
+    let received = Arc::new(AtomicI32::new(0));
+    let link_received = received.clone();
+    rux_fires::on_shutdown(move |signal| link_received.store(signal, Ordering::Release)).unwrap();
+    rux_fires::shutdown_clean(&temp).unwrap();
+    rux_fires::signal_pid(std::process::id(), rux_fires::SIGTERM).unwrap();
+    for _ in 0..100 {
+        if received.load(Ordering::Acquire) != 0 {
+            break;
+        }
+        rux_fires::sleep(10);
+    }
+    assert_eq!(received.load(Ordering::Acquire), rux_fires::SIGTERM);
+    assert!(rux_fires::is_shutdown());
+    assert_eq!(rux_fires::get_shutdown_signal(), Some(rux_fires::SIGTERM));
+    rux_fires::sleep(50);
+    assert!(!std::path::Path::new(&temp).exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn signal_exit_test() {
+    let vars = [("RUBX_SIGNAL_EXIT_CHILD", "1")];
+    let status = run_child("tests::fires::signal_exit_child", &vars);
+    assert_eq!(status.code(), Some(128 + crate::rux_fires::SIGTERM));
+}
+
+#[cfg(unix)]
+#[test]
+#[ignore]
+fn signal_exit_child() {
+    use crate::rux_fires;
+    if std::env::var_os("RUBX_SIGNAL_EXIT_CHILD").is_none() {
+        return;
+    }
+    rux_fires::set_signal_forward(false);
+    rux_fires::on_shutdown(|_| rux_fires::sleep(5000)).unwrap();
+    rux_fires::signal_pid(std::process::id(), rux_fires::SIGTERM).unwrap();
+    for _ in 0..100 {
+        if rux_fires::is_shutdown() {
+            break;
+        }
+        rux_fires::sleep(10);
+    }
+    rux_fires::signal_pid(std::process::id(), rux_fires::SIGTERM).unwrap();
+    rux_fires::sleep(5000);
+}
+
+#[cfg(all(test, unix))]
+fn run_child(
+    name: &str,
+    vars: &[(&str, impl AsRef<std::ffi::OsStr>)],
+) -> std::process::ExitStatus {
+    let mut command = std::process::Command::new(std::env::current_exe().unwrap());
+    command.args(["--exact", name, "--ignored"]);
+    for (key, value) in vars {
+        command.env(key, value);
+    }
+    command
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .unwrap()
+}
+
+#[test]
+fn lock_test() {
+    use crate::rux_fires;
+    let dir = format!("{}", std::env::temp_dir().display());
+    {
+        let held = rux_fires::lock("rubx_lock_test", Some(&dir)).unwrap();
+        assert!(std::path::Path::new(held.pid_path()).exists());
+        assert!(rux_fires::try_lock("rubx_lock_test", Some(&dir)).unwrap().is_none());
+        assert!(rux_fires::lock("rubx_lock_test", Some(&dir)).is_err());
+        assert_eq!(
+            rux_fires::lock_owner("rubx_lock_test", Some(&dir)).unwrap(),
+            Some(std::process::id())
+        );
+    }
+    assert_eq!(rux_fires::lock_owner("rubx_lock_test", Some(&dir)).unwrap(), None);
+    assert!(rux_fires::try_lock("rubx_lock_test", Some(&dir)).unwrap().is_some());
+}
+
+#[test]
+fn pool_test() {
+    use crate::rux_fires;
+    let pool = rux_fires::pool(Some(3)).unwrap();
+    assert_eq!(pool.size(), 3);
+    let names = pool
+        .map((0..6).collect::<Vec<i32>>(), |_| {
+            std::thread::current().name().unwrap_or_default().to_string()
+        })
+        .unwrap();
+    let workers = ["rubx-worker-0", "rubx-worker-1", "rubx-worker-2"];
+    assert!(names.iter().all(|name| workers.contains(&name.as_str())));
+    let doubled = pool.map(vec![1, 2, 3], |item| item * 2).unwrap();
+    assert_eq!(doubled, vec![2, 4, 6]);
+    let offsets = vec![10, 20, 30];
+    let shifted = pool.map(vec![0, 1, 2], |index| offsets[index] + 1).unwrap();
+    assert_eq!(shifted, vec![11, 21, 31]);
+    let task = pool.submit(|| -> i32 { panic!("pool test panic") }).unwrap();
+    let err = task.join().unwrap_err();
+    assert!(format!("{}", err).contains("pool test panic"));
+    let err = pool.map(vec![0, 1, 0], |item| 10 / item).unwrap_err();
+    assert!(format!("{}", err).contains("2 of 3 jobs failed"));
+    pool.shutdown().unwrap();
+}
+
+#[test]
+fn spawn_detached_test() {
+    use crate::rux_fires;
+    let base = std::env::temp_dir();
+    let out_path = format!("{}", base.join("rubx_detached_test.out").display());
+    let pid_path = format!("{}", base.join("rubx_detached_test.pid").display());
+    let _ = std::fs::remove_file(&out_path);
+    let pid = rux_fires::spawn_detached(
+        "sh",
+        &["-c", "echo detached"],
+        None::<&str>,
+        Some(&out_path),
+        None,
+        Some(&pid_path),
+    )
+    .unwrap();
+    assert_eq!(std::fs::read_to_string(&pid_path).unwrap().trim(), pid.to_string());
+    for _ in 0..100 {
+        if std::fs::read_to_string(&out_path).unwrap_or_default().contains("detached") {
+            break;
+        }
+        rux_fires::sleep(10);
+    }
+    assert_eq!(std::fs::read_to_string(&out_path).unwrap().trim(), "detached");
+    if rux_fires::is_lin() {
+        let proc_path = format!("/proc/{}", pid);
+        for _ in 0..100 {
+            if !std::path::Path::new(&proc_path).exists() {
+                break;
+            }
+            rux_fires::sleep(10);
+        }
+        assert!(!std::path::Path::new(&proc_path).exists());
+    }
+    std::fs::remove_file(&out_path).unwrap();
+    std::fs::remove_file(&pid_path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_usage_test() {
+    use crate::rux_fires;
+    let (code, output, usage) =
+        rux_fires::cmd_usage("sh", &["-c", "echo used"], None::<&str>, Some(false), None).unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(output, "used");
+    assert!(usage.wall.as_nanos() > 0);
+    assert!(usage.max_rss > 0);
+    assert_eq!(usage.signal, None);
+    let (code, _, usage) = rux_fires::cmd_usage(
+        "sh",
+        &["-c", "kill -9 $$"],
+        None::<&str>,
+        Some(false),
+        Some(false),
+    )
+    .unwrap();
+    assert_eq!(code, 128 + rux_fires::SIGKILL);
+    assert_eq!(usage.signal, Some(rux_fires::SIGKILL));
+    let result = rux_fires::cmd("sh", &["-c", "kill -9 $$"], None::<&str>, Some(false), None);
+    assert!(format!("{}", result.unwrap_err()).contains("terminated by a signal"));
+}
+
+#[cfg(unix)]
+#[test]
+fn cmd_as_test() {
+    use crate::{rux_fires, rux_hosts};
+    let (_, uid) = rux_fires::cmd("id", &["-u"], None::<&str>, Some(false), None).unwrap();
+    assert_eq!(rux_hosts::get_user_id().unwrap().to_string(), uid);
+    assert_eq!(rux_fires::get_uid().unwrap().to_string(), uid);
+    assert_eq!(rux_fires::get_username().unwrap(), rux_hosts::get_user_name().unwrap());
+    let (_, gid) = rux_fires::cmd("id", &["-g"], None::<&str>, Some(false), None).unwrap();
+    assert_eq!(rux_fires::get_egid().unwrap().to_string(), gid);
+    if let Ok(myself) = rux_fires::CmdUser::of(&rux_fires::get_username().unwrap()) {
+        assert!(myself.groups.clone().unwrap().contains(&myself.gid.unwrap()));
+        if myself.uid == Some(rux_fires::get_euid().unwrap())
+            && myself.gid == Some(rux_fires::get_gid().unwrap())
+        {
+            let (_, same) =
+                rux_fires::cmd_as("id", &["-u"], None::<&str>, Some(false), None, &myself).unwrap();
+            assert_eq!(same, uid);
+        }
+    }
+    assert_eq!(rux_fires::is_elevated(), rux_fires::get_euid().unwrap() == 0);
+    let (_, same) = rux_fires::cmd_as(
+        "id",
+        &["-u"],
+        None::<&str>,
+        Some(false),
+        None,
+        &rux_fires::CmdUser::default(),
+    )
+    .unwrap();
+    assert_eq!(same, uid);
+    if rux_fires::is_elevated() {
+        if let Ok(nobody) = rux_fires::CmdUser::of("nobody") {
+            let (_, other) =
+                rux_fires::cmd_as("id", &["-u"], Some("/"), Some(false), None, &nobody).unwrap();
+            assert_eq!(other, nobody.uid.unwrap().to_string());
+        }
+    }
+}
@@ -0,0 +1,15 @@
+#[test]
+fn get_test() {
+    use crate::rux_hosts;
+    let info = rux_hosts::get();
+    assert_eq!(info.os, std::env::consts::OS);
+    assert!(info.cpus_logical > 0);
+    let json = info.to_json();
+    assert_eq!(json["arch"], std::env::consts::ARCH);
+    assert_eq!(json["cpus_logical"], info.cpus_logical);
+    if cfg!(target_os = "linux") {
+        assert!(info.hostname.is_some());
+        assert!(info.memory_total.unwrap() >= info.memory_available.unwrap());
+        assert!(info.uptime.unwrap() > 0.0);
+    }
+}
@@ -1 +1,5 @@
-mod paths;
\ No newline at end of file
+mod fires;
+mod hosts;
+mod paths;
+mod tasks;
+mod texts;
\ No newline at end of file
@@ -65,4 +65,745 @@ fn path_absolute_test() {
     let expect = format!("{}{}test", wd, rux_paths::os_sep());
     let result = rux_paths::path_absolute(tester).unwrap();
     assert_eq!(result, expect);
-}
\ No newline at end of file
+}
+
+#[test]
+fn matches_test() {
+    use crate::rux_paths;
+    assert!(rux_paths::matches("*.rs", "lib.rs"));
+    assert!(!rux_paths::matches("*.rs", "src/lib.rs"));
+    assert!(rux_paths::matches("src/**/*.rs", "src/lib.rs"));
+    assert!(rux_paths::matches("src/**/*.rs", "src/tests/paths.rs"));
+    assert!(rux_paths::matches("src/**", "src/tests/paths.rs"));
+    assert!(rux_paths::matches("fil?.t[xy]t", "file.txt"));
+    assert!(!rux_paths::matches("fil?.t[!xy]t", "file.txt"));
+    assert!(rux_paths::matches("[a-c]*", "bravo"));
+    assert!(rux_paths::matches("*.{rs,toml}", "Cargo.toml"));
+    assert!(rux_paths::matches("{src,tests}/{a,b}.rs", "tests/b.rs"));
+    assert!(!rux_paths::matches("{src,tests}/{a,b}.rs", "tests/c.rs"));
+    assert!(rux_paths::matches("/usr/*/lib", "/usr/local/lib"));
+    assert!(!rux_paths::matches("/usr/*/lib", "usr/local/lib"));
+    assert!(rux_paths::matches("\\*.rs", "*.rs"));
+    assert!(!rux_paths::matches("\\*.rs", "a.rs"));
+    assert!(!rux_paths::matches("*.rs", ".hidden.rs"));
+    assert!(rux_paths::matches(".*.rs", ".hidden.rs"));
+    assert!(!rux_paths::matches("src/**/*.rs", "src/.cache/lib.rs"));
+    assert!(rux_paths::matches("src/.cache/*.rs", "src/.cache/lib.rs"));
+    #[cfg(windows)]
+    assert!(rux_paths::matches("src/*.rs", "src\\lib.rs"));
+}
+
+#[test]
+fn glob_test() {
+    use crate::rux_paths;
+    let results = rux_paths::glob("src/**/*.rs")
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert!(results.iter().any(|path| rux_paths::matches("src/lib.rs", path)));
+    assert!(results.iter().any(|path| rux_paths::matches("src/tests/paths.rs", path)));
+    assert!(results.iter().all(|path| rux_paths::path_ext_is(path, ".rs")));
+    let results = rux_paths::glob("*.{toml,md}")
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert!(results.contains(&String::from("Cargo.toml")));
+    assert!(results.contains(&String::from("README.md")));
+    assert!(!results.iter().any(|path| path.contains("src")));
+    let results = rux_paths::glob("Cargo.toml")
+        .unwrap()
+        .collect::<Result<Vec<String>, _>>()
+        .unwrap();
+    assert_eq!(results, vec![String::from("Cargo.toml")]);
+    #[cfg(unix)]
+    {
+        let base = std::env::temp_dir().join("rubx_glob_test");
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("real").join("deep")).unwrap();
+        std::fs::write(base.join("real").join("deep").join("file.rs"), "file").unwrap();
+        std::os::unix::fs::symlink("real", base.join("link")).unwrap();
+        std::os::unix::fs::symlink("..", base.join("real").join("back")).unwrap();
+        let mut results = rux_paths::glob(&format!("{}/**/*.rs", base.display()))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        results.sort();
+        let expect = vec![
+            format!("{}", base.join("link").join("deep").join("file.rs").display()),
+            format!("{}", base.join("real").join("deep").join("file.rs").display()),
+        ];
+        assert_eq!(results, expect);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
+
+#[cfg(test)]
+fn walk_names(walker: crate::rux_paths::PathWalker, base: &std::path::Path) -> Vec<String> {
+    walker
+        .into_iter()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let inside = entry.path.strip_prefix(base).unwrap();
+            inside.to_string_lossy().replace('\\', "/")
+        })
+        .collect()
+}
+
+#[test]
+fn walk_test() {
+    use crate::rux_paths;
+    let base = std::env::temp_dir().join("rubx_walk_test");
+    let _ = std::fs::remove_dir_all(&base);
+    std::fs::create_dir_all(base.join("alpha").join("beta")).unwrap();
+    std::fs::create_dir_all(base.join(".hidden")).unwrap();
+    std::fs::write(base.join("one.txt"), "one").unwrap();
+    std::fs::write(base.join("alpha").join("two.rs"), "two").unwrap();
+    std::fs::write(base.join("alpha").join("beta").join("three.txt"), "three").unwrap();
+    std::fs::write(base.join(".hidden").join("four.txt"), "four").unwrap();
+    let root = format!("{}", base.display());
+    let names = |walker: rux_paths::PathWalker| walk_names(walker, &base);
+    assert_eq!(
+        names(rux_paths::walk(&root).sort(true).hidden(false)),
+        vec!["", "alpha", "alpha/beta", "alpha/beta/three.txt", "alpha/two.rs", "one.txt"]
+    );
+    assert_eq!(
+        names(rux_paths::walk(&root).sort(true).min_depth(1).max_depth(1)),
+        vec![".hidden", "alpha", "one.txt"]
+    );
+    assert_eq!(
+        names(rux_paths::walk(&root).sort(true).post_order(true).hidden(false)),
+        vec!["alpha/beta/three.txt", "alpha/beta", "alpha/two.rs", "alpha", "one.txt", ""]
+    );
+    assert_eq!(
+        names(
+            rux_paths::walk(&root)
+                .sort(true)
+                .exclude(|entry| entry.name() == "beta")
+                .include(|entry| entry.is_file)
+        ),
+        vec![".hidden/four.txt", "alpha/two.rs", "one.txt"]
+    );
+    let mut files = rux_paths::path_list_files_exts_in(&root, &[".TXT"]).unwrap();
+    files.sort();
+    assert_eq!(files.len(), 3);
+    assert!(files[0].ends_with("four.txt"));
+    let dirs = rux_paths::path_list_dirs_in(&root).unwrap();
+    assert_eq!(dirs.len(), 3);
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&base, base.join("alpha").join("back")).unwrap();
+        assert_eq!(rux_paths::path_list_in(&root).unwrap().len(), 8);
+        let looped = rux_paths::walk(&root)
+            .follow_links(true)
+            .into_iter()
+            .any(|entry| entry.is_err());
+        assert!(looped);
+    }
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn path_ignore_test() {
+    use crate::rux_paths::PathIgnore;
+    let ignore = PathIgnore::parse(
+        "# comment\ntarget/\n*.log\n!keep.log\n/root_only.txt\ndocs/**/draft.md\n\\#hash\n",
+    );
+    assert!(ignore.is_ignored("target", true));
+    assert!(!ignore.is_ignored("target", false));
+    assert!(ignore.is_ignored("sub/target", true));
+    assert!(ignore.is_ignored("debug.log", false));
+    assert!(ignore.is_ignored("deep/inside/debug.log", false));
+    assert!(!ignore.is_ignored("keep.log", false));
+    assert_eq!(ignore.matched("keep.log", false), Some(false));
+    assert!(ignore.is_ignored("root_only.txt", false));
+    assert!(!ignore.is_ignored("sub/root_only.txt", false));
+    assert!(ignore.is_ignored("docs/draft.md", false));
+    assert!(ignore.is_ignored("docs/a/b/draft.md", false));
+    assert!(ignore.is_ignored("#hash", false));
+    assert_eq!(ignore.matched("src/lib.rs", false), None);
+}
+
+#[test]
+fn walk_git_ignore_test() {
+    use crate::rux_paths;
+    let base = std::env::temp_dir().join("rubx_walk_ignore_test");
+    let _ = std::fs::remove_dir_all(&base);
+    std::fs::create_dir_all(base.join("target").join("debug")).unwrap();
+    std::fs::create_dir_all(base.join("sub")).unwrap();
+    std::fs::create_dir_all(base.join(".git")).unwrap();
+    std::fs::write(base.join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+    std::fs::write(base.join(".rubxignore"), "extra.txt\n").unwrap();
+    std::fs::write(base.join("sub").join(".gitignore"), "local.txt\n!again.log\n").unwrap();
+    for file in [
+        "main.rs",
+        "debug.log",
+        "keep.log",
+        "extra.txt",
+        "target/debug/out.bin",
+        "sub/local.txt",
+        "sub/again.log",
+        "sub/other.txt",
+        ".git/HEAD",
+    ] {
+        std::fs::write(base.join(file), "data").unwrap();
+    }
+    let root = format!("{}", base.display());
+    let names = |walker: rux_paths::PathWalker| {
+        walk_names(
+            walker
+                .min_depth(1)
+                .sort(true)
+                .include(|entry| entry.is_file && !entry.name().starts_with(".gitignore")),
+            &base,
+        )
+    };
+    assert_eq!(
+        names(rux_paths::walk(&root).git_ignore(true).ignore_file(".rubxignore")),
+        vec![".rubxignore", "keep.log", "main.rs", "sub/again.log", "sub/other.txt"]
+    );
+    assert_eq!(names(rux_paths::walk(&root)).len(), 10);
+    let files = rux_paths::walk(&root)
+        .git_ignore(true)
+        .include(|entry| entry.is_file)
+        .list()
+        .unwrap();
+    assert_eq!(files.len(), 8);
+    assert!(files.iter().all(|file| !file.contains("target") && !file.ends_with("debug.log")));
+    let logs = rux_paths::walk(&root)
+        .git_ignore(true)
+        .include(|entry| entry.is_file && entry.name().ends_with(".log"))
+        .list()
+        .unwrap();
+    assert_eq!(logs.len(), 2);
+    assert_eq!(rux_paths::path_list_files_ext_in(&root, ".log").unwrap().len(), 3);
+    let dirs = rux_paths::walk(&root)
+        .min_depth(1)
+        .git_ignore(true)
+        .include(|entry| entry.is_dir)
+        .list()
+        .unwrap();
+    assert_eq!(dirs.len(), 1);
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn walk_parallel_test() {
+    use crate::rux_paths;
+    use std::sync::Mutex;
+    let base = std::env::temp_dir().join("rubx_walk_parallel_test");
+    let _ = std::fs::remove_dir_all(&base);
+    for first in 0..4 {
+        for second in 0..4 {
+            let dir = base.join(format!("d{}", first)).join(format!("d{}", second));
+            std::fs::create_dir_all(&dir).unwrap();
+            for file in 0..3 {
+                std::fs::write(dir.join(format!("f{}.txt", file)), "data").unwrap();
+            }
+        }
+    }
+    std::fs::write(base.join(".gitignore"), "f2.txt\n").unwrap();
+    let root = format!("{}", base.display());
+    let mut expected = rux_paths::walk(&root).git_ignore(true).list().unwrap();
+    expected.sort();
+    let founds = Mutex::new(Vec::new());
+    rux_paths::walk(&root)
+        .git_ignore(true)
+        .run(Some(4), |entry| {
+            let entry = entry.unwrap();
+            founds.lock().unwrap().push(format!("{}", entry.path.display()));
+        })
+        .unwrap();
+    let mut founds = founds.into_inner().unwrap();
+    founds.sort();
+    assert_eq!(founds, expected);
+    assert_eq!(founds.len(), 1 + 1 + 4 + 16 + 32);
+    let mut received = rux_paths::walk(&root)
+        .min_depth(1)
+        .include(|entry| entry.is_file)
+        .channel(None)
+        .into_iter()
+        .map(|entry| entry.unwrap().name())
+        .collect::<Vec<String>>();
+    received.sort();
+    assert_eq!(received.len(), 49);
+    assert_eq!(received[0], ".gitignore");
+    assert!(rux_paths::walk(&root).post_order(true).run(None, |_| {}).is_err());
+    let panicked = rux_paths::walk(&root).run(Some(4), |entry| {
+        if entry.unwrap().name() == "d2" {
+            panic!("walk parallel test panic");
+        }
+    });
+    assert!(panicked.is_err());
+    let mut visited = 0;
+    rux_paths::traverse(&base, &mut |_| visited += 1).unwrap();
+    assert_eq!(visited, 1 + 1 + 4 + 16 + 48);
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn rubx_path_test() {
+    use crate::rux_paths::{self, RubxPath};
+    use std::path::Path;
+    let tester = RubxPath::new("src/lib.rs");
+    assert_eq!(tester.name().unwrap(), "lib.rs");
+    assert_eq!(tester.stem().unwrap(), "lib");
+    assert_eq!(tester.ext().unwrap(), "rs");
+    assert!(tester.ext_is(".RS"));
+    assert_eq!(tester.parts().len(), 2);
+    let absolute = tester.absolute().unwrap();
+    assert!(absolute.is_absolute());
+    assert!(absolute.is_file());
+    assert_eq!(tester.parent().unwrap().name().unwrap(), "src");
+    let joined = RubxPath::new("src/tests").join("../rux_paths.rs").unwrap();
+    assert_eq!(joined.as_path(), Path::new("src/rux_paths.rs"));
+    assert!(RubxPath::new("/abs").join("/other").is_err());
+    let relative = absolute.relative(rux_paths::wd().unwrap()).unwrap();
+    assert_eq!(relative.as_path(), Path::new("./src/lib.rs"));
+    assert_eq!(rux_paths::path_name("src/lib.rs"), "lib.rs");
+    assert_eq!(rux_paths::path_stem("src/lib.rs"), "lib");
+    assert_eq!(rux_paths::path_ext("src/lib.rs"), ".rs");
+    assert_eq!(rux_paths::path_ext("src/Makefile"), "");
+    assert_eq!(rux_paths::path_ext("home/.config.toml"), ".toml");
+    assert_eq!(RubxPath::new("src/Makefile").stem().unwrap(), "Makefile");
+    assert_eq!(RubxPath::new("home/.bashrc").stem().unwrap(), ".bashrc");
+    assert!(RubxPath::new("home/.bashrc").ext().is_none());
+    assert_eq!(RubxPath::new("src/lib.rs").sep(), std::path::MAIN_SEPARATOR_STR);
+    #[cfg(unix)]
+    {
+        let back = RubxPath::new("dir/back\\slash.txt");
+        assert_eq!(back.name().unwrap(), "back\\slash.txt");
+        assert_eq!(back.sep(), "/");
+        use std::os::unix::ffi::OsStrExt;
+        let name = std::ffi::OsStr::from_bytes(b"bad\xffname.txt");
+        let raw = RubxPath::new("dir").join(name).unwrap();
+        assert_eq!(raw.name().unwrap(), name);
+        assert!(raw.ext_is("txt"));
+        assert!(raw.to_text().is_err());
+        let base = std::env::temp_dir().join("rubx_list_unicode_test");
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("sub")).unwrap();
+        std::fs::write(base.join("good.txt"), "good").unwrap();
+        std::fs::write(base.join(name), "bad").unwrap();
+        std::fs::write(base.join("sub").join(name), "bad").unwrap();
+        let base_text = base.to_str().unwrap();
+        assert_eq!(rux_paths::path_list_files(base_text).unwrap().len(), 1);
+        assert_eq!(rux_paths::path_list_files_ext(base_text, ".txt").unwrap().len(), 1);
+        assert_eq!(rux_paths::path_list_files_in(base_text).unwrap().len(), 1);
+        assert_eq!(rux_paths::path_list_files_ext_in(base_text, ".txt").unwrap().len(), 1);
+        assert_eq!(rux_paths::path_list_in(base_text).unwrap().len(), 2);
+        let pattern = format!("{}/**/*.txt", base_text);
+        let found = rux_paths::glob(&pattern).unwrap().collect::<Result<Vec<String>, _>>();
+        assert_eq!(found.unwrap().len(), 1);
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
+
+#[test]
+fn path_normalize_test() {
+    use crate::rux_paths;
+    let sep = rux_paths::os_sep();
+    assert_eq!(
+        rux_paths::path_normalize("a//b/./c/../d").unwrap(),
+        format!("a{}b{}d", sep, sep)
+    );
+    assert_eq!(rux_paths::path_normalize("./a/..").unwrap(), ".");
+    assert_eq!(
+        rux_paths::path_normalize("../a/../../b").unwrap(),
+        format!("..{}..{}b", sep, sep)
+    );
+    #[cfg(unix)]
+    {
+        assert_eq!(rux_paths::path_normalize("/../a/./b/").unwrap(), "/a/b");
+        assert_eq!(rux_paths::path_normalize("/a/b/../../..").unwrap(), "/");
+        use std::os::unix::ffi::OsStrExt;
+        let name = std::ffi::OsStr::from_bytes(b"bad\xffname.txt");
+        let raw = rux_paths::RubxPath::new("dir").join(name).unwrap();
+        assert!(raw.normalize().to_text().is_err());
+    }
+}
+
+#[test]
+fn path_relative_test() {
+    use crate::rux_paths;
+    let sep = rux_paths::os_sep();
+    let wd = rux_paths::wd().unwrap();
+    let lib = rux_paths::path_absolute("src/lib.rs").unwrap();
+    assert_eq!(
+        rux_paths::path_relative(&lib, &wd).unwrap(),
+        format!(".{}src{}lib.rs", sep, sep)
+    );
+    assert_eq!(
+        rux_paths::path_relative("Cargo.toml", "src/tests").unwrap(),
+        format!("..{}..{}Cargo.toml", sep, sep)
+    );
+    assert_eq!(
+        rux_paths::path_relative("src/lib.rs", "src/tests").unwrap(),
+        format!("..{}lib.rs", sep)
+    );
+    assert_eq!(rux_paths::path_relative("src", "src/./").unwrap(), ".");
+    assert_eq!(
+        rux_paths::path_relative("src/lib.rs", &wd).unwrap(),
+        format!(".{}src{}lib.rs", sep, sep)
+    );
+    let wd_name = rux_paths::path_name(&wd);
+    assert_eq!(
+        rux_paths::path_relative("src/lib.rs", "..").unwrap(),
+        format!(".{}{}{}src{}lib.rs", sep, wd_name, sep, sep)
+    );
+    #[cfg(unix)]
+    {
+        assert_eq!(rux_paths::path_relative("/a/b/c", "/a/x/y").unwrap(), "../../b/c");
+        assert_eq!(rux_paths::path_relative("/", "/a").unwrap(), "..");
+    }
+}
+
+#[test]
+fn path_canonical_test() {
+    use crate::rux_paths;
+    let canonical = rux_paths::path_canonical("src/../Cargo.toml").unwrap();
+    assert!(rux_paths::is_absolute(&canonical));
+    assert_eq!(rux_paths::path_name(&canonical), "Cargo.toml");
+    assert!(rux_paths::path_canonical("src/missing-file").is_err());
+    #[cfg(unix)]
+    {
+        let base = std::env::temp_dir().join("rubx_canonical_test");
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("real")).unwrap();
+        std::os::unix::fs::symlink(base.join("real"), base.join("link")).unwrap();
+        let linked = format!("{}/link/../link", base.display());
+        let canonical = rux_paths::path_canonical(&linked).unwrap();
+        assert!(canonical.ends_with("/real"));
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
+
+#[test]
+fn path_parse_test() {
+    use crate::rux_paths::{self, PathPrefix, PathStyle};
+    let tester = rux_paths::path_parse("C:\\pointel//test\\.\\file.txt", PathStyle::Windows);
+    assert_eq!(tester.prefix, Some(PathPrefix::Disk('C')));
+    assert!(tester.rooted);
+    assert!(tester.is_absolute());
+    assert_eq!(tester.parts, vec!["pointel", "test", "file.txt"]);
+    assert_eq!(tester.to_text(), "C:\\pointel\\test\\file.txt");
+    assert_eq!(tester.name(), Some("file.txt"));
+    assert_eq!(tester.parent().unwrap().to_text(), "C:\\pointel\\test");
+    let tester = rux_paths::path_parse("c:relative\\file", PathStyle::Windows);
+    assert_eq!(tester.prefix, Some(PathPrefix::Disk('C')));
+    assert!(!tester.rooted);
+    assert!(tester.is_relative());
+    let tester = rux_paths::path_parse("\\\\server\\share\\dir\\file", PathStyle::Windows);
+    assert_eq!(
+        tester.prefix,
+        Some(PathPrefix::Unc("server".into(), "share".into()))
+    );
+    assert!(tester.is_absolute());
+    assert_eq!(tester.parts, vec!["dir", "file"]);
+    assert_eq!(tester.to_text(), "\\\\server\\share\\dir\\file");
+    let tester = rux_paths::path_parse("//server/share/dir", PathStyle::Windows);
+    assert_eq!(
+        tester.prefix,
+        Some(PathPrefix::Unc("server".into(), "share".into()))
+    );
+    let tester = rux_paths::path_parse("\\\\?\\C:\\dir/with/slashes", PathStyle::Windows);
+    assert_eq!(tester.prefix, Some(PathPrefix::VerbatimDisk('C')));
+    assert_eq!(tester.parts, vec!["dir/with/slashes"]);
+    let tester = rux_paths::path_parse("\\\\?\\UNC\\server\\share\\file", PathStyle::Windows);
+    assert_eq!(
+        tester.prefix,
+        Some(PathPrefix::VerbatimUnc("server".into(), "share".into()))
+    );
+    assert_eq!(tester.to_text(), "\\\\?\\UNC\\server\\share\\file");
+    let tester = rux_paths::path_parse("\\\\.\\COM1", PathStyle::Windows);
+    assert_eq!(tester.prefix, Some(PathPrefix::Device("COM1".into())));
+    let tester = rux_paths::path_parse("\\\\?\\日本\\..\\file", PathStyle::Windows);
+    assert_eq!(tester.prefix, Some(PathPrefix::Verbatim("日本".into())));
+    assert_eq!(tester.normalize().parts, vec!["..", "file"]);
+    let tester = rux_paths::path_parse("\\rooted\\file", PathStyle::Windows);
+    assert_eq!(tester.prefix, None);
+    assert!(tester.rooted);
+    assert!(tester.is_relative());
+    let tester = rux_paths::path_parse("/home/back\\slash", PathStyle::Posix);
+    assert!(tester.is_absolute());
+    assert_eq!(tester.parts, vec!["home", "back\\slash"]);
+    let tester = rux_paths::path_parse("C:\\file", PathStyle::Posix);
+    assert!(tester.is_relative());
+    assert_eq!(tester.parts, vec!["C:\\file"]);
+}
+
+#[test]
+fn path_parse_join_test() {
+    use crate::rux_paths::{self, PathStyle};
+    let base = rux_paths::path_parse("C:\\base\\dir", PathStyle::Windows);
+    assert_eq!(base.join("..\\other").normalize().to_text(), "C:\\base\\other");
+    assert_eq!(base.join("\\root").to_text(), "C:\\root");
+    assert_eq!(base.join("D:\\disk").to_text(), "D:\\disk");
+    assert_eq!(
+        base.to_style(PathStyle::Posix).to_text(),
+        "C:/base/dir"
+    );
+    let base = rux_paths::path_parse("/usr/local", PathStyle::Posix);
+    assert_eq!(base.join("../../..").normalize().to_text(), "/");
+    assert_eq!(base.join("/etc").to_text(), "/etc");
+    let base = rux_paths::path_parse("a/b", PathStyle::Posix);
+    assert_eq!(base.join("../../../c").normalize().to_text(), "../c");
+    assert_eq!(rux_paths::path_parse("./", PathStyle::Posix).to_text(), ".");
+    assert_eq!(rux_paths::path_parse("..", PathStyle::Posix).parent(), None);
+}
+
+#[test]
+fn write_atomic_test() {
+    use crate::rux_paths;
+    let base = std::env::temp_dir().join("rubx_write_atomic_test");
+    let _ = std::fs::remove_dir_all(&base);
+    std::fs::create_dir_all(&base).unwrap();
+    let target = format!("{}", base.join("target.txt").display());
+    rux_paths::write_atomic(&target, "first").unwrap();
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "first");
+    rux_paths::write_atomic(&target, b"second").unwrap();
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "second");
+    let failed = rux_paths::write_atomic_with(&target, |file| {
+        use std::io::Write;
+        file.write_all(b"partial").unwrap();
+        Err("interrupted".into())
+    });
+    assert!(failed.is_err());
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "second");
+    assert_eq!(std::fs::read_dir(&base).unwrap().count(), 1);
+    let source = format!("{}", base.join("source.txt").display());
+    std::fs::write(&source, "replaced").unwrap();
+    rux_paths::replace(&source, &target).unwrap();
+    assert_eq!(std::fs::read_to_string(&target).unwrap(), "replaced");
+    assert_eq!(std::fs::read_dir(&base).unwrap().count(), 2);
+    std::fs::remove_file(&source).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o640)).unwrap();
+        let link = format!("{}", base.join("link.txt").display());
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        rux_paths::write_atomic(&link, "third").unwrap();
+        assert!(rux_paths::is_symlink(&link));
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "third");
+        let mode = std::fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn cp_with_test() {
+    use crate::rux_paths::{self, CopyExists, CopySetup};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    let base = std::env::temp_dir().join("rubx_cp_with_test");
+    let _ = std::fs::remove_dir_all(&base);
+    let origin = base.join("origin");
+    std::fs::create_dir_all(origin.join("inner")).unwrap();
+    std::fs::create_dir_all(origin.join("skipped")).unwrap();
+    std::fs::write(origin.join("one.txt"), "one").unwrap();
+    std::fs::write(origin.join("two.log"), "two-two").unwrap();
+    std::fs::write(origin.join("inner").join("three.txt"), "three").unwrap();
+    std::fs::write(origin.join("skipped").join("four.txt"), "four").unwrap();
+    std::fs::create_dir_all(origin.join("logs")).unwrap();
+    std::fs::write(origin.join("logs").join("five.log"), "five").unwrap();
+    let origin_str = format!("{}", origin.display());
+    let destiny = base.join("destiny");
+    let destiny_str = format!("{}", destiny.display());
+    let setup = CopySetup::default()
+        .dry_run(true)
+        .exclude(|entry| entry.name() == "skipped")
+        .include(|entry| entry.name().ends_with(".txt"));
+    let report = rux_paths::cp_with(&origin_str, &destiny_str, &setup).unwrap();
+    assert_eq!(report.files, 2);
+    assert_eq!(report.bytes, 8);
+    assert_eq!(
+        report.copied,
+        vec![destiny.join("inner").join("three.txt"), destiny.join("one.txt")]
+    );
+    assert!(!destiny.exists());
+    let filtered = base.join("filtered");
+    let filtered_str = format!("{}", filtered.display());
+    let setup = CopySetup::default()
+        .exclude(|entry| entry.name() == "skipped")
+        .include(|entry| entry.name().ends_with(".txt"));
+    let report = rux_paths::cp_with(&origin_str, &filtered_str, &setup).unwrap();
+    assert_eq!(report.files, 2);
+    assert!(filtered.join("inner").join("three.txt").exists());
+    assert!(!filtered.join("logs").exists());
+    assert!(!filtered.join("skipped").exists());
+    let notified = Arc::new(AtomicU64::new(0));
+    let link_notified = notified.clone();
+    let setup = CopySetup::default()
+        .preserve_mtime(true)
+        .progress(move |progress| link_notified.store(progress.bytes, Ordering::Release));
+    let report = rux_paths::cp_with(&origin_str, &destiny_str, &setup).unwrap();
+    assert_eq!(report.files, 5);
+    assert_eq!(report.bytes, 23);
+    assert_eq!(notified.load(Ordering::Acquire), 23);
+    let origin_time = std::fs::metadata(origin.join("two.log")).unwrap().modified().unwrap();
+    let destiny_time = std::fs::metadata(destiny.join("two.log")).unwrap().modified().unwrap();
+    assert_eq!(origin_time, destiny_time);
+    std::fs::write(destiny.join("one.txt"), "changed").unwrap();
+    let setup = CopySetup::default().exists(CopyExists::Skip);
+    let report = rux_paths::cp_with(&origin_str, &destiny_str, &setup).unwrap();
+    assert_eq!(report.files, 0);
+    assert_eq!(report.skipped.len(), 5);
+    assert_eq!(std::fs::read_to_string(destiny.join("one.txt")).unwrap(), "changed");
+    let setup = CopySetup::default().exists(CopyExists::OverwriteIfNewer);
+    let report = rux_paths::cp_with(&origin_str, &destiny_str, &setup).unwrap();
+    assert_eq!(report.files, 0);
+    std::fs::write(origin.join(".hidden"), "hidden").unwrap();
+    let setup = CopySetup::default().hidden(false);
+    rux_paths::cp_with(&origin_str, &destiny_str, &setup).unwrap();
+    assert!(!destiny.join(".hidden").exists());
+    let setup = CopySetup::default().exists(CopyExists::Error);
+    assert!(rux_paths::cp_with(&origin_str, &destiny_str, &setup).is_err());
+    rux_paths::cp(&origin_str, &destiny_str).unwrap();
+    assert_eq!(std::fs::read_to_string(destiny.join("one.txt")).unwrap(), "one");
+    std::fs::write(destiny.join("one.txt"), "kept").unwrap();
+    std::fs::hard_link(destiny.join("one.txt"), base.join("held.txt")).unwrap();
+    let mut readonly = std::fs::metadata(destiny.join("one.txt")).unwrap().permissions();
+    readonly.set_readonly(true);
+    std::fs::set_permissions(destiny.join("one.txt"), readonly).unwrap();
+    let setup = CopySetup::default().preserve_mode(false).preserve_mtime(true);
+    rux_paths::cp_with(&origin_str, &destiny_str, &setup).unwrap();
+    assert_eq!(std::fs::read_to_string(destiny.join("one.txt")).unwrap(), "one");
+    assert_eq!(std::fs::read_to_string(base.join("held.txt")).unwrap(), "kept");
+    assert!(std::fs::metadata(destiny.join("one.txt")).unwrap().permissions().readonly());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let script = origin.join("run.sh");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o750)).unwrap();
+        std::os::unix::fs::symlink("one.txt", origin.join("alias.txt")).unwrap();
+        std::os::unix::fs::symlink("inner", origin.join("alias")).unwrap();
+        let linked = base.join("linked");
+        let linked_str = format!("{}", linked.display());
+        let setup = CopySetup::default().preserve_links(true);
+        rux_paths::cp_with(&origin_str, &linked_str, &setup).unwrap();
+        assert!(rux_paths::is_symlink(&format!("{}", linked.join("alias.txt").display())));
+        let mode = std::fs::metadata(linked.join("run.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+        let followed = base.join("followed");
+        let followed_str = format!("{}", followed.display());
+        rux_paths::cp(&origin_str, &followed_str).unwrap();
+        assert!(!rux_paths::is_symlink(&format!("{}", followed.join("alias.txt").display())));
+        assert_eq!(std::fs::read_to_string(followed.join("alias.txt")).unwrap(), "one");
+        assert!(rux_paths::is_symlink(&format!("{}", followed.join("alias").display())));
+        assert!(followed.join(".hidden").exists());
+        std::fs::write(followed.join("alias.txt"), "newer").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(origin.join("one.txt"), "one-newer").unwrap();
+        let setup = CopySetup::default().exists(CopyExists::OverwriteIfNewer);
+        rux_paths::cp_with(&origin_str, &followed_str, &setup).unwrap();
+        assert_eq!(std::fs::read_to_string(followed.join("alias.txt")).unwrap(), "one-newer");
+        let deep = base.join("deep");
+        let deep_str = format!("{}", deep.display());
+        let setup = CopySetup::default().follow_links(true);
+        rux_paths::cp_with(&origin_str, &deep_str, &setup).unwrap();
+        assert!(!rux_paths::is_symlink(&format!("{}", deep.join("alias").display())));
+        assert!(deep.join("alias").join("three.txt").exists());
+    }
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn mv_test() {
+    use crate::rux_paths;
+    let base = std::env::temp_dir().join("rubx_mv_test");
+    let _ = std::fs::remove_dir_all(&base);
+    std::fs::create_dir_all(base.join("tree").join("inner")).unwrap();
+    std::fs::create_dir_all(base.join("into")).unwrap();
+    std::fs::write(base.join("tree").join("inner").join("file.txt"), "data").unwrap();
+    let text = |path: std::path::PathBuf| format!("{}", path.display());
+    #[cfg(unix)]
+    let inode = {
+        use std::os::unix::fs::MetadataExt;
+        std::fs::metadata(base.join("tree").join("inner").join("file.txt"))
+            .unwrap()
+            .ino()
+    };
+    rux_paths::mv(&text(base.join("tree")), &text(base.join("moved"))).unwrap();
+    assert!(!base.join("tree").exists());
+    let moved_file = base.join("moved").join("inner").join("file.txt");
+    assert_eq!(std::fs::read_to_string(&moved_file).unwrap(), "data");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        assert_eq!(std::fs::metadata(&moved_file).unwrap().ino(), inode);
+    }
+    rux_paths::mv(&text(moved_file), &text(base.join("into"))).unwrap();
+    assert!(base.join("into").join("file.txt").is_file());
+    std::fs::write(base.join("moved").join("full.txt"), "full").unwrap();
+    std::fs::create_dir_all(base.join("into").join("inner")).unwrap();
+    std::fs::write(base.join("into").join("inner").join("more.txt"), "more").unwrap();
+    rux_paths::mv(&text(base.join("into")), &text(base.join("moved"))).unwrap();
+    assert!(!base.join("into").exists());
+    assert!(base.join("moved").join("full.txt").is_file());
+    assert!(base.join("moved").join("file.txt").is_file());
+    assert!(base.join("moved").join("inner").join("more.txt").is_file());
+    std::fs::write(base.join("single.txt"), "single").unwrap();
+    assert!(rux_paths::rn(&text(base.join("single.txt")), &text(base.join("moved"))).is_err());
+    rux_paths::rn(
+        &text(base.join("single.txt")),
+        &text(base.join("moved").join("single.txt")),
+    )
+    .unwrap();
+    assert!(base.join("moved").join("single.txt").is_file());
+    std::fs::create_dir_all(base.join("again")).unwrap();
+    assert!(rux_paths::rn(&text(base.join("again")), &text(base.join("moved"))).is_err());
+    std::fs::write(base.join("again").join("first.txt"), "first").unwrap();
+    std::fs::create_dir_all(base.join("again").join("single.txt")).unwrap();
+    assert!(rux_paths::mv(&text(base.join("again")), &text(base.join("moved"))).is_err());
+    assert!(base.join("again").join("first.txt").is_file());
+    assert!(!base.join("moved").join("first.txt").exists());
+    assert!(base.join("moved").join("single.txt").is_file());
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn mv_across_test() {
+    use crate::rux_paths;
+    use std::os::unix::fs::MetadataExt;
+    let other = std::path::Path::new("/dev/shm");
+    let local = std::env::temp_dir();
+    let crosses = match (std::fs::metadata(other), std::fs::metadata(&local)) {
+        (Ok(other_meta), Ok(local_meta)) => other_meta.dev() != local_meta.dev(),
+        _ => false,
+    };
+    if !crosses {
+        return;
+    }
+    let base = local.join("rubx_mv_across_test");
+    let remote = other.join("rubx_mv_across_test");
+    let _ = std::fs::remove_dir_all(&base);
+    let _ = std::fs::remove_dir_all(&remote);
+    std::fs::create_dir_all(base.join("tree").join("inner")).unwrap();
+    std::fs::create_dir_all(&remote).unwrap();
+    std::fs::write(base.join("tree").join("inner").join("file.txt"), "data").unwrap();
+    std::fs::write(base.join("single.txt"), "single").unwrap();
+    std::os::unix::fs::symlink("inner/file.txt", base.join("tree").join("alias")).unwrap();
+    let text = |path: std::path::PathBuf| format!("{}", path.display());
+    rux_paths::mv(&text(base.join("tree")), &text(remote.join("tree"))).unwrap();
+    assert!(!base.join("tree").exists());
+    assert_eq!(
+        std::fs::read_to_string(remote.join("tree").join("inner").join("file.txt")).unwrap(),
+        "data"
+    );
+    assert!(remote.join("tree").join("alias").is_symlink());
+    rux_paths::rn(&text(base.join("single.txt")), &text(remote.join("single.txt"))).unwrap();
+    assert!(!base.join("single.txt").exists());
+    assert_eq!(std::fs::read_to_string(remote.join("single.txt")).unwrap(), "single");
+    std::fs::create_dir_all(base.join("again")).unwrap();
+    std::fs::write(base.join("again").join("file.txt"), "again").unwrap();
+    rux_paths::mv(&text(base.join("again")), &text(remote.join("tree"))).unwrap();
+    assert!(!base.join("again").exists());
+    assert_eq!(std::fs::read_to_string(remote.join("tree").join("file.txt")).unwrap(), "again");
+    assert!(remote.join("tree").join("inner").join("file.txt").is_file());
+    assert_eq!(std::fs::read_dir(&remote).unwrap().count(), 2);
+    std::fs::remove_dir_all(&base).unwrap();
+    std::fs::remove_dir_all(&remote).unwrap();
+}
@@ -0,0 +1,43 @@
+#[test]
+fn cron_test() {
+    use crate::rux_tasks::Cron;
+    use chrono::{TimeZone, Utc};
+    let cron = Cron::parse("*/15 9-17 * * 1-5").unwrap();
+    let start = Utc.with_ymd_and_hms(2022, 6, 24, 17, 50, 0).unwrap();
+    let next = cron.next_after(&start).unwrap();
+    assert_eq!(next, Utc.with_ymd_and_hms(2022, 6, 27, 9, 0, 0).unwrap());
+    assert!(cron.matches(&next));
+    let cron = Cron::parse("@monthly").unwrap();
+    let next = cron.next_after(&start).unwrap();
+    assert_eq!(next, Utc.with_ymd_and_hms(2022, 7, 1, 0, 0, 0).unwrap());
+    let cron = Cron::parse("30 12 13 * 5").unwrap();
+    let next = cron.next_after(&start).unwrap();
+    assert_eq!(next, Utc.with_ymd_and_hms(2022, 7, 1, 12, 30, 0).unwrap());
+    let cron = Cron::parse("0 0 */2 * 1").unwrap();
+    let next = cron.next_after(&start).unwrap();
+    assert_eq!(next, Utc.with_ymd_and_hms(2022, 6, 27, 0, 0, 0).unwrap());
+    assert!(Cron::parse("61 * * * *").is_err());
+    assert!(Cron::parse("* * *").is_err());
+}
+
+#[test]
+fn every_test() {
+    use crate::rux_tasks;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    assert!(rux_tasks::every("every-zero", 0, || Ok(())).is_err());
+    let counter = Arc::new(AtomicUsize::new(0));
+    let link_counter = counter.clone();
+    let handle = rux_tasks::every("every-test", 20, move || {
+        link_counter.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    })
+    .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(150));
+    assert!(handle.is_running());
+    handle.stop();
+    let runs = handle.runs();
+    handle.join().unwrap();
+    assert!(runs >= 2);
+    assert_eq!(counter.load(Ordering::Acquire), runs);
+}
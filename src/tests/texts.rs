@@ -0,0 +1,77 @@
+#[test]
+fn split_words_test() {
+    use crate::rux_texts;
+    let tester = rux_texts::split_words("git commit -m 'first commit'", None).unwrap();
+    assert_eq!(tester, vec!["git", "commit", "-m", "first commit"]);
+    let tester = rux_texts::split_words(r#"echo "say \"hi\"" a\ b ''"#, None).unwrap();
+    assert_eq!(tester, vec!["echo", "say \"hi\"", "a b", ""]);
+    std::env::set_var("RUBX_SPLIT_WORDS_TEST", "value");
+    let tester = rux_texts::split_words(
+        "echo $RUBX_SPLIT_WORDS_TEST \"${RUBX_SPLIT_WORDS_TEST}s\" '$RUBX_SPLIT_WORDS_TEST'",
+        Some(true),
+    )
+    .unwrap();
+    assert_eq!(tester, vec!["echo", "value", "values", "$RUBX_SPLIT_WORDS_TEST"]);
+    assert!(rux_texts::split_words("echo 'open", None).is_err());
+    assert!(rux_texts::split_words("echo ${HOME rest of line", Some(true)).is_err());
+}
+
+#[test]
+fn join_words_test() {
+    use crate::rux_texts;
+    assert_eq!(rux_texts::quote_word("simple"), "simple");
+    assert_eq!(rux_texts::quote_word(""), "''");
+    assert_eq!(rux_texts::quote_word("it's"), "'it'\\''s'");
+    let words = vec!["echo", "two words", "it's"];
+    let joined = rux_texts::join_words(&words);
+    assert_eq!(rux_texts::split_words(&joined, None).unwrap(), words);
+}
+
+#[test]
+fn text_files_find_any_test() {
+    use crate::rux_texts;
+    let base = std::env::temp_dir().join("rubx_find_any_test");
+    std::fs::create_dir_all(&base).unwrap();
+    let first = format!("{}", base.join("first.txt").display());
+    let second = format!("{}", base.join("second.txt").display());
+    std::fs::write(&first, "alpha\nbeta\n").unwrap();
+    std::fs::write(&second, "gamma\nbeta\n").unwrap();
+    let founds = rux_texts::text_files_find_any(
+        vec![first.clone(), second.clone()],
+        vec!["beta".to_string()],
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(founds.len(), 2);
+    assert_eq!(rux_texts::text_file_founds(&founds[0])[0], first);
+    assert_eq!(rux_texts::text_file_founds(&founds[1])[0], second);
+    let founds = rux_texts::text_files_find_any(
+        vec![first.clone(), second.clone(), first.clone(), second.clone()],
+        vec!["beta".to_string()],
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(founds.len(), 4);
+    assert_eq!(rux_texts::text_file_founds(&founds[3])[0], second);
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn write_atomic_test() {
+    use crate::rux_texts;
+    let base = std::env::temp_dir().join("rubx_texts_atomic_test");
+    let _ = std::fs::remove_dir_all(&base);
+    std::fs::create_dir_all(&base).unwrap();
+    let path = format!("{}", base.join("lines.txt").display());
+    rux_texts::write(&path, String::from("plain")).unwrap();
+    assert_eq!(rux_texts::read(&path).unwrap(), "plain");
+    rux_texts::write_atomic(&path, String::from("atomic")).unwrap();
+    assert_eq!(rux_texts::read(&path).unwrap(), "atomic");
+    let lines = vec![String::from("alpha"), String::from("beta")];
+    rux_texts::write_lines_atomic(&path, lines.clone()).unwrap();
+    assert_eq!(rux_texts::read(&path).unwrap(), "alpha\nbeta\n");
+    rux_texts::write_lines(&path, lines).unwrap();
+    assert_eq!(rux_texts::read(&path).unwrap(), "alpha\nbeta\n");
+    assert_eq!(std::fs::read_dir(&base).unwrap().count(), 1);
+    std::fs::remove_dir_all(&base).unwrap();
+}